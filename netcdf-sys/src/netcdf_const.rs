@@ -214,6 +214,8 @@ pub const NC_CLOBBER: ::std::os::raw::c_int = 0;
 pub const NC_NOCLOBBER: ::std::os::raw::c_int = 4;
 pub const NC_DISKLESS: ::std::os::raw::c_int = 8;
 pub const NC_MMAP: ::std::os::raw::c_int = 16;
+pub const NC_64BIT_DATA: ::std::os::raw::c_int = 32;
+pub const NC_CDF5: ::std::os::raw::c_int = 32;
 pub const NC_CLASSIC_MODEL: ::std::os::raw::c_int = 256;
 pub const NC_64BIT_OFFSET: ::std::os::raw::c_int = 512;
 pub const NC_LOCK: ::std::os::raw::c_int = 1024;
@@ -226,6 +228,9 @@ pub const NC_FORMAT_CLASSIC: ::std::os::raw::c_int = 1;
 pub const NC_FORMAT_64BIT: ::std::os::raw::c_int = 2;
 pub const NC_FORMAT_NETCDF4: ::std::os::raw::c_int = 3;
 pub const NC_FORMAT_NETCDF4_CLASSIC: ::std::os::raw::c_int = 4;
+pub const NC_FORMAT_64BIT_OFFSET: ::std::os::raw::c_int = 2;
+pub const NC_FORMAT_64BIT_DATA: ::std::os::raw::c_int = 5;
+pub const NC_FORMAT_CDF5: ::std::os::raw::c_int = 5;
 pub const NC_FORMAT_NC3: ::std::os::raw::c_int = 1;
 pub const NC_FORMAT_NC_HDF5: ::std::os::raw::c_int = 2;
 pub const NC_FORMAT_NC_HDF4: ::std::os::raw::c_int = 3;
@@ -247,6 +252,7 @@ pub const NC_ENDIAN_LITTLE: ::std::os::raw::c_int = 1;
 pub const NC_ENDIAN_BIG: ::std::os::raw::c_int = 2;
 pub const NC_CHUNKED: ::std::os::raw::c_int = 0;
 pub const NC_CONTIGUOUS: ::std::os::raw::c_int = 1;
+pub const NC_COMPACT: ::std::os::raw::c_int = 2;
 pub const NC_NOCHECKSUM: ::std::os::raw::c_int = 0;
 pub const NC_FLETCHER32: ::std::os::raw::c_int = 1;
 pub const NC_NOSHUFFLE: ::std::os::raw::c_int = 0;
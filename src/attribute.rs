@@ -1,16 +1,19 @@
 use std::fmt;
 use std::ffi;
+use std::ptr;
 use std::collections::HashMap;
 use netcdf_sys::*;
-use string_from_c_str;
-use NC_ERRORS;
+use libc;
+use {c_string, define_mode, string_from_c_str};
+use error::{Error, Result};
+use variable::Numeric;
 
 macro_rules! get_attr_as_type {
     ( $me:ident, $nc_type:ident, $rs_type:ty, $nc_fn:ident , $cast:ident ) 
         =>
     {{
         if (!$cast) && ($me.attrtype != $nc_type) {
-            return Err("Types are not equivalent and cast==false".to_string());
+            return Err(Error::TypeMismatch { expected: $nc_type, found: $me.attrtype });
         }
         let mut err: i32;
         let mut attlen : u64 = 0;
@@ -22,10 +25,10 @@ macro_rules! get_attr_as_type {
                                     &mut attlen);
         }
         if err != NC_NOERR {
-            return Err(NC_ERRORS.get(&err).unwrap().clone());
+            return Err(Error::netcdf(err, "nc_inq_attlen", $me.name.clone()));
         }
         if attlen != 1 {
-            return Err("Multi-value attributes not yet implemented".to_string());
+            return Err(Error::AttributeNotScalar { name: $me.name.clone(), len: attlen });
         }
         let mut buf: $rs_type = 0 as $rs_type;
         unsafe {
@@ -35,12 +38,117 @@ macro_rules! get_attr_as_type {
                                        &mut buf);
         }
         if err != NC_NOERR {
-            return Err(NC_ERRORS.get(&err).unwrap().clone());
+            return Err(Error::netcdf(err, stringify!($nc_fn), $me.name.clone()));
         }
         Ok(buf)
     }}
 }
 
+/// The values of an attribute, for each netCDF atomic type
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    /// NC_CHAR
+    Str(String),
+    /// NC_STRING
+    Strs(Vec<String>),
+    /// NC_BYTE
+    I8s(Vec<i8>),
+    /// NC_UBYTE
+    U8s(Vec<u8>),
+    /// NC_SHORT
+    Shorts(Vec<i16>),
+    /// NC_USHORT
+    Ushorts(Vec<u16>),
+    /// NC_INT
+    Ints(Vec<i32>),
+    /// NC_UINT
+    Uints(Vec<u32>),
+    /// NC_INT64
+    Longlongs(Vec<i64>),
+    /// NC_UINT64
+    Ulonglongs(Vec<u64>),
+    /// NC_FLOAT
+    Floats(Vec<f32>),
+    /// NC_DOUBLE
+    Doubles(Vec<f64>),
+}
+
+// Writes a comma separated list of values, with a CDL type suffix
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter, values: &[T], suffix: &str) -> fmt::Result {
+    for (i, v) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}{}", v, suffix)?;
+    }
+    Ok(())
+}
+
+// Formats floating point values the way ncdump does (`1.`, `1e+20`, `NaN`, `Infinity`),
+// from their shortest round-trip representation
+fn cdl_float<T: fmt::Debug>(v: T) -> String {
+    let s = format!("{:?}", v);
+    match s.as_str() {
+        "NaN" => return s,
+        "inf" => return "Infinity".to_string(),
+        "-inf" => return "-Infinity".to_string(),
+        _ => {}
+    }
+    let s = if s.ends_with(".0") { s[..s.len() - 1].to_string() } else { s };
+    match s.find('e') {
+        Some(i) if !s[i + 1..].starts_with('-') => format!("{}e+{}", &s[..i], &s[i + 1..]),
+        _ => s,
+    }
+}
+
+// Quotes and escapes text the way ncdump does
+fn cdl_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\0' => out.push_str("\\0"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl fmt::Display for AttributeValue {
+    /// Formats the values like ncdump (CDL syntax), e.g. `1s, 2s` or `"degrees_north"`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AttributeValue::Str(ref v) => write!(f, "{}", cdl_string(v)),
+            AttributeValue::Strs(ref v) => {
+                let v: Vec<String> = v.iter().map(|s| cdl_string(s)).collect();
+                write_list(f, &v, "")
+            }
+            AttributeValue::I8s(ref v) => write_list(f, v, "b"),
+            AttributeValue::U8s(ref v) => write_list(f, v, "ub"),
+            AttributeValue::Shorts(ref v) => write_list(f, v, "s"),
+            AttributeValue::Ushorts(ref v) => write_list(f, v, "us"),
+            AttributeValue::Ints(ref v) => write_list(f, v, ""),
+            AttributeValue::Uints(ref v) => write_list(f, v, "u"),
+            AttributeValue::Longlongs(ref v) => write_list(f, v, "LL"),
+            AttributeValue::Ulonglongs(ref v) => write_list(f, v, "ULL"),
+            AttributeValue::Floats(ref v) => {
+                let v: Vec<String> = v.iter().map(|x| cdl_float(*x)).collect();
+                write_list(f, &v, "f")
+            }
+            AttributeValue::Doubles(ref v) => {
+                let v: Vec<String> = v.iter().map(|x| cdl_float(*x)).collect();
+                write_list(f, &v, "")
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Attribute {
     pub name : String,
     pub attrtype : i32,
@@ -50,9 +158,103 @@ pub struct Attribute {
 }
 
 impl Attribute {
-    pub fn get_char(&self, cast: bool) -> Result<String, String> {
+    /// Number of values stored in the attribute
+    pub(crate) fn attlen(&self) -> Result<u64> {
+        let mut attlen : u64 = 0;
+        let name_c: ffi::CString = ffi::CString::new(self.name.clone()).unwrap();
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_inq_attlen(self.file_id, self.var_id, name_c.as_ptr(), &mut attlen);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_attlen", self.name.clone()));
+        }
+        Ok(attlen)
+    }
+
+    /// Text of a NC_CHAR attribute, or first string of a NC_STRING attribute
+    pub(crate) fn text(&self) -> Result<String> {
+        if self.attrtype == NC_STRING {
+            return Ok(self.get_strings()?.into_iter().next().unwrap_or_default());
+        }
+        self.get_char(false)
+    }
+
+    /// Fetchs all the attribute values, and cast them if needed.
+    ///
+    /// ```
+    /// // let valid_range: Vec<f32> = some_attribute.values().unwrap();
+    /// ```
+    ///
+    pub fn values<T: Numeric>(&self) -> Result<Vec<T>> {
+        T::from_attribute(self)
+    }
+
+    /// Fetchs the attribute values, whatever their type.
+    ///
+    /// ```
+    /// // match some_attribute.value().unwrap() {
+    /// //     AttributeValue::Str(s) => println!("text: {}", s),
+    /// //     other => println!("values: {}", other),
+    /// // }
+    /// ```
+    ///
+    pub fn value(&self) -> Result<AttributeValue> {
+        Ok(match self.attrtype {
+            NC_CHAR => AttributeValue::Str(self.get_char(false)?),
+            NC_STRING => AttributeValue::Strs(self.get_strings()?),
+            NC_BYTE => AttributeValue::I8s(self.values()?),
+            NC_UBYTE => AttributeValue::U8s(self.values()?),
+            NC_SHORT => AttributeValue::Shorts(self.values()?),
+            NC_USHORT => AttributeValue::Ushorts(self.values()?),
+            NC_INT => AttributeValue::Ints(self.values()?),
+            NC_UINT => AttributeValue::Uints(self.values()?),
+            NC_INT64 => AttributeValue::Longlongs(self.values()?),
+            NC_UINT64 => AttributeValue::Ulonglongs(self.values()?),
+            NC_FLOAT => AttributeValue::Floats(self.values()?),
+            NC_DOUBLE => AttributeValue::Doubles(self.values()?),
+            other => return Err(Error::UnsupportedType {
+                name: self.name.clone(),
+                nctype: other
+            }),
+        })
+    }
+
+    /// Fetchs the values of a NC_STRING attribute
+    ///
+    /// Strings are not required to be UTF-8, invalid sequences are replaced by U+FFFD.
+    pub fn get_strings(&self) -> Result<Vec<String>> {
+        if self.attrtype != NC_STRING {
+            return Err(Error::TypeMismatch { expected: NC_STRING, found: self.attrtype });
+        }
+        let attlen = self.attlen()? as usize;
+        let name_c: ffi::CString = ffi::CString::new(self.name.clone()).unwrap();
+        let mut ptrs: Vec<*mut libc::c_char> = vec![ptr::null_mut(); attlen];
+        let strings: Vec<String>;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            let err = nc_get_att_string(self.file_id, self.var_id, name_c.as_ptr(),
+                                        ptrs.as_mut_ptr());
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_get_att_string", self.name.clone()));
+            }
+            // copy the strings before handing the memory back to libnetcdf
+            strings = ptrs.iter().map(|p| {
+                if p.is_null() {
+                    String::new()
+                } else {
+                    ffi::CStr::from_ptr(*p).to_string_lossy().into_owned()
+                }
+            }).collect();
+            nc_free_string(attlen as u64, ptrs.as_mut_ptr());
+        }
+        Ok(strings)
+    }
+
+    pub fn get_char(&self, cast: bool) -> Result<String> {
         if (!cast) && (self.attrtype != NC_CHAR) {
-            return Err("Types are not equivalent and cast==false".to_string());
+            return Err(Error::TypeMismatch { expected: NC_CHAR, found: self.attrtype });
         }
         let attr_char_str;
         let name_copy: ffi::CString = 
@@ -66,7 +268,7 @@ impl Attribute {
                                         &mut attlen);
             }
             if err != NC_NOERR {
-                return Err(NC_ERRORS.get(&err).unwrap().clone());
+                return Err(Error::netcdf(err, "nc_inq_attlen", self.name.clone()));
             }
             // careful; netcdf does not write null terminators here
             let mut attr_char_buf_vec = vec![0i8; (attlen+1) as usize];
@@ -78,71 +280,165 @@ impl Attribute {
                                             attr_char_buf_ptr);
             }
             if err != NC_NOERR {
-                return Err(NC_ERRORS.get(&err).unwrap().clone());
+                return Err(Error::netcdf(err, "nc_get_att_text", self.name.clone()));
             }
             let attr_c_str = ffi::CStr::from_ptr(attr_char_buf_ptr);
-            attr_char_str = string_from_c_str(attr_c_str);
+            attr_char_str = string_from_c_str(attr_c_str)?;
         }
         Ok(attr_char_str)
     }
 
-    pub fn get_byte(&self, cast: bool) -> Result<i8, String> {
+    pub fn get_byte(&self, cast: bool) -> Result<i8> {
         get_attr_as_type!(self, NC_BYTE, i8, nc_get_att_schar, cast)
     }
 
-    pub fn get_short(&self, cast: bool) -> Result<i16, String> {
+    pub fn get_short(&self, cast: bool) -> Result<i16> {
         get_attr_as_type!(self, NC_SHORT, i16, nc_get_att_short, cast)
     }
 
-    pub fn get_ushort(&self, cast: bool) -> Result<u16, String> {
+    pub fn get_ushort(&self, cast: bool) -> Result<u16> {
         get_attr_as_type!(self, NC_USHORT, u16, nc_get_att_ushort, cast)
     }
 
-    pub fn get_int(&self, cast: bool) -> Result<i32, String> {
+    pub fn get_int(&self, cast: bool) -> Result<i32> {
         get_attr_as_type!(self, NC_INT, i32, nc_get_att_int, cast)
     }
 
-    pub fn get_uint(&self, cast: bool) -> Result<u32, String> {
+    pub fn get_uint(&self, cast: bool) -> Result<u32> {
         get_attr_as_type!(self, NC_UINT, u32, nc_get_att_uint, cast)
     }
 
-    pub fn get_int64(&self, cast: bool) -> Result<i64, String> {
+    pub fn get_int64(&self, cast: bool) -> Result<i64> {
         get_attr_as_type!(self, NC_INT64, i64, nc_get_att_longlong, cast)
     }
 
-    pub fn get_uint64(&self, cast: bool) -> Result<u64, String> {
+    pub fn get_uint64(&self, cast: bool) -> Result<u64> {
         get_attr_as_type!(self, NC_UINT64, u64, nc_get_att_ulonglong, cast)
     }
 
-    pub fn get_float(&self, cast: bool) -> Result<f32, String> {
+    pub fn get_float(&self, cast: bool) -> Result<f32> {
         get_attr_as_type!(self, NC_FLOAT, f32, nc_get_att_float, cast)
     }
 
-    pub fn get_double(&self, cast: bool) -> Result<f64, String> {
+    pub fn get_double(&self, cast: bool) -> Result<f64> {
         get_attr_as_type!(self, NC_DOUBLE, f64, nc_get_att_double, cast)
     }
 }
 
 impl fmt::Display for Attribute {
+    /// Formats the attribute values like ncdump does
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.get_char(true) {
-            Ok(chars) => write!(f, "{}", chars),
+        match self.value() {
+            Ok(value) => write!(f, "{}", value),
             Err(e) => write!(f, "ERROR: {}", e)
         }
     }
 }
 
+/// Copy the attributes in `attrs` to the variable `var_id` of the group `file_id`
+/// (`nc_copy_att`), in their original order, and add them to `out_attrs`.
+pub(crate) fn copy_attributes(attrs: &HashMap<String, Attribute>,
+                              out_attrs: &mut HashMap<String, Attribute>,
+                              file_id: i32, var_id: i32) -> Result<()> {
+    define_mode(file_id)?;
+    let mut sorted: Vec<&Attribute> = attrs.values().collect();
+    sorted.sort_by_key(|attr| attr.id);
+    for attr in sorted {
+        let name_c: ffi::CString = ffi::CString::new(attr.name.clone()).unwrap();
+        let mut id: i32 = 0;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            let err = nc_copy_att(attr.file_id, attr.var_id, name_c.as_ptr(), file_id, var_id);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_copy_att", attr.name.clone()));
+            }
+            let err = nc_inq_attid(file_id, var_id, name_c.as_ptr(), &mut id);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_inq_attid", attr.name.clone()));
+            }
+        }
+        out_attrs.insert(attr.name.clone(), Attribute {
+            name: attr.name.clone(),
+            attrtype: attr.attrtype,
+            id,
+            var_id,
+            file_id,
+        });
+    }
+    Ok(())
+}
+
+/// Rename the attribute `old_name` of the variable `var_id` (`NC_GLOBAL` for
+/// group attributes) of the group `file_id`, updating `attrs`.
+pub(crate) fn rename_attribute(attrs: &mut HashMap<String, Attribute>, file_id: i32, var_id: i32,
+                               old_name: &str, new_name: &str) -> Result<()> {
+    if !attrs.contains_key(old_name) {
+        return Err(Error::AttributeNotFound(old_name.to_string()));
+    }
+    let old_name_c: ffi::CString = c_string(old_name)?;
+    let new_name_c: ffi::CString = c_string(new_name)?;
+    define_mode(file_id)?;
+    let err: i32;
+    unsafe {
+        let _g = libnetcdf_lock.lock().unwrap();
+        err = nc_rename_att(file_id, var_id, old_name_c.as_ptr(), new_name_c.as_ptr());
+    }
+    if err != NC_NOERR {
+        return Err(Error::netcdf(err, "nc_rename_att", old_name));
+    }
+    let mut attr = attrs.remove(old_name).unwrap();
+    attr.name = new_name.to_string();
+    attrs.insert(new_name.to_string(), attr);
+    Ok(())
+}
+
+/// Delete the attribute `name` of the variable `var_id` (`NC_GLOBAL` for
+/// group attributes) of the group `file_id`, updating `attrs`.
+pub(crate) fn delete_attribute(attrs: &mut HashMap<String, Attribute>, file_id: i32, var_id: i32,
+                               name: &str) -> Result<()> {
+    if !attrs.contains_key(name) {
+        return Err(Error::AttributeNotFound(name.to_string()));
+    }
+    let name_c: ffi::CString = c_string(name)?;
+    define_mode(file_id)?;
+    let err: i32;
+    unsafe {
+        let _g = libnetcdf_lock.lock().unwrap();
+        err = nc_del_att(file_id, var_id, name_c.as_ptr());
+    }
+    if err != NC_NOERR {
+        return Err(Error::netcdf(err, "nc_del_att", name));
+    }
+    attrs.remove(name);
+    // the following attributes are renumbered
+    for attr in attrs.values_mut() {
+        let name_c: ffi::CString = ffi::CString::new(attr.name.clone()).unwrap();
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_inq_attid(file_id, var_id, name_c.as_ptr(), &mut attr.id);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_attid", attr.name.clone()));
+        }
+    }
+    Ok(())
+}
+
 pub fn init_attributes(attrs: &mut HashMap<String, Attribute>, 
                    file_id: i32, 
                    var_id: i32,
-                   natts_in: i32) { // TODO: better interface to indicate these are var attrs
+                   natts_in: i32) -> Result<()> { // TODO: better interface to indicate these are var attrs
     let mut nattrs = 0i32;
     if natts_in == -1 {
         // these are global attrs; have to determine number of attrs
+        let err: i32;
         unsafe {
             let _g = libnetcdf_lock.lock().unwrap();
-            let err = nc_inq_natts(file_id, &mut nattrs);
-            assert_eq!(err, NC_NOERR);
+            err = nc_inq_natts(file_id, &mut nattrs);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_natts", format!("ncid {}", file_id)));
         }
     } else {
         nattrs = natts_in;
@@ -157,17 +453,22 @@ pub fn init_attributes(attrs: &mut HashMap<String, Attribute>,
             let _g = libnetcdf_lock.lock().unwrap();
             let name_buf_ptr : *mut i8 = name_buf_vec.as_mut_ptr();
             let err = nc_inq_attname(file_id, var_id, i_attr, name_buf_ptr);
-            assert_eq!(err, NC_NOERR);
-            let err = nc_inq_atttype(file_id, var_id, name_buf_ptr, &mut attr_type);
-            assert_eq!(err, NC_NOERR);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_inq_attname", format!("attnum {}", i_attr)));
+            }
             name_c_str = ffi::CStr::from_ptr(name_buf_ptr);
+            let err = nc_inq_atttype(file_id, var_id, name_buf_ptr, &mut attr_type);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_inq_atttype", name_c_str.to_string_lossy().into_owned()));
+            }
         }
-        let name_str: String = string_from_c_str(name_c_str);
+        let name_str: String = string_from_c_str(name_c_str)?;
         attrs.insert(name_str.clone(),
                       Attribute{name: name_str.clone(),
                           attrtype: attr_type,
                           id: i_attr,
-                          var_id: var_id,
-                          file_id: file_id});
+                          var_id,
+                          file_id});
     }
+    Ok(())
 }
@@ -0,0 +1,311 @@
+//! Checks of the [CF conventions](http://cfconventions.org/Data/cf-conventions/cf-conventions-1.7/cf-conventions.html).
+//!
+//! ```no_run
+//! # let file = netcdf::open("forecast.nc").unwrap();
+//! let report = netcdf::cf::check(&file).unwrap();
+//! for issue in &report.issues {
+//!     println!("{}", issue);
+//! }
+//! assert!(report.is_compliant());
+//! ```
+//!
+//! Standard names are only checked for their syntax, not against the standard name table.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use netcdf_sys::*;
+use attribute::Attribute;
+use error::Result;
+use file::File;
+use group::Group;
+use time::{Calendar, TimeUnits};
+use variable::Variable;
+
+/// Modifiers allowed after a standard name
+const STANDARD_NAME_MODIFIERS: [&str; 4] = [
+    "detection_minimum", "number_of_observations", "standard_error", "status_flag"];
+
+/// Attributes holding values of the variable type
+const TYPED_ATTRIBUTES: [&str; 5] = [
+    "_FillValue", "missing_value", "valid_min", "valid_max", "valid_range"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The file does not follow the conventions
+    Error,
+    /// The file follows the conventions, but misses recommended information
+    Warning,
+}
+
+/// A deviation from the CF conventions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// No global `Conventions` attribute
+    MissingConventions,
+    /// The global `Conventions` attribute does not name a CF version
+    InvalidConventions(String),
+    /// A name not made of letters, digits and underscores, starting with a letter
+    InvalidName(String),
+    /// A numeric variable without `units`
+    MissingUnits,
+    /// An attribute of the wrong type
+    AttributeType { name: String, expected: i32, found: i32 },
+    /// A malformed `standard_name`
+    InvalidStandardName(String),
+    /// A `coordinates`, `bounds`, `grid_mapping` or `ancillary_variables`
+    /// attribute naming a missing variable
+    MissingVariable { attribute: String, name: String },
+    /// Time `units` that can't be parsed
+    InvalidTimeUnits(String),
+    /// An unknown `calendar`
+    InvalidCalendar(String),
+    /// A `bounds` variable whose dimensions aren't the variable dimensions
+    /// followed by the number of vertices
+    BoundsShape { bounds: String, dimensions: Vec<String>, bounds_dimensions: Vec<String> },
+    /// A coordinate variable with missing values
+    CoordinateMissingValues,
+    /// A coordinate variable whose values are not strictly monotonic
+    CoordinateNotMonotonic,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::MissingConventions => write!(f, "no Conventions attribute"),
+            Violation::InvalidConventions(ref value) => write!(
+                f, "Conventions attribute '{}' does not name a CF version", value),
+            Violation::InvalidName(ref name) => write!(
+                f, "'{}' should be made of letters, digits and underscores, starting with a letter", name),
+            Violation::MissingUnits => write!(f, "no units attribute"),
+            Violation::AttributeType { ref name, expected, found } => write!(
+                f, "attribute '{}' has the type {}, expected {}", name, found, expected),
+            Violation::InvalidStandardName(ref value) => write!(f, "invalid standard_name '{}'", value),
+            Violation::MissingVariable { ref attribute, ref name } => write!(
+                f, "variable '{}' named by the {} attribute not found", name, attribute),
+            Violation::InvalidTimeUnits(ref units) => write!(f, "invalid time units '{}'", units),
+            Violation::InvalidCalendar(ref calendar) => write!(f, "unknown calendar '{}'", calendar),
+            Violation::BoundsShape { ref bounds, ref dimensions, ref bounds_dimensions } => write!(
+                f, "bounds variable '{}' has the dimensions ({}), expected ({}, n_vertices)",
+                bounds, bounds_dimensions.join(", "), dimensions.join(", ")),
+            Violation::CoordinateMissingValues => write!(f, "coordinate variable with missing values"),
+            Violation::CoordinateNotMonotonic => write!(
+                f, "coordinate variable values are not strictly monotonic"),
+        }
+    }
+}
+
+/// A violation found in a group or variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    /// Path of the group (ending with `/`) or variable
+    pub location: String,
+    pub violation: Violation,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARNING",
+        };
+        write!(f, "{}: {}: {}", severity, self.location, self.violation)
+    }
+}
+
+/// Issues found by `check`, ordered by location.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    /// Returns `true` if no error was found, warnings being allowed.
+    pub fn is_compliant(&self) -> bool {
+        self.errors().is_empty()
+    }
+
+    pub fn errors(&self) -> Vec<&Issue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Error).collect()
+    }
+
+    pub fn warnings(&self) -> Vec<&Issue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Warning).collect()
+    }
+
+    fn push(&mut self, severity: Severity, location: &str, violation: Violation) {
+        self.issues.push(Issue { severity, location: location.to_string(), violation });
+    }
+}
+
+/// Checks the file against the CF conventions, walking all its groups.
+pub fn check(file: &File) -> Result<Report> {
+    let mut report = Report::default();
+    match text_attribute(&file.root.attributes, "Conventions", "/", &mut report)? {
+        Some(ref conventions) if conventions
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .any(|c| c.starts_with("CF-")) => {}
+        Some(conventions) => report.push(Severity::Warning, "/", Violation::InvalidConventions(conventions)),
+        None => report.push(Severity::Warning, "/", Violation::MissingConventions),
+    }
+    check_group(&file.root, "/", &[], &mut report)?;
+    Ok(report)
+}
+
+fn check_group(group: &Group, path: &str, ancestors: &[&Group], report: &mut Report) -> Result<()> {
+    let mut scope = ancestors.to_vec();
+    scope.push(group);
+
+    let mut dim_names: Vec<&String> = group.dimensions.keys().collect();
+    dim_names.sort();
+    for name in dim_names {
+        // dimensions of the parent groups are checked there
+        if !ancestors.iter().any(|g| g.dimensions.contains_key(name)) && !is_valid_name(name) {
+            report.push(Severity::Warning, path, Violation::InvalidName(name.clone()));
+        }
+    }
+
+    // bounds and grid mapping variables don't need units
+    let mut unitless: HashSet<String> = HashSet::new();
+    for var in group.variables.values() {
+        for attribute in &["bounds", "grid_mapping"] {
+            if let Some(attr) = var.attributes.get(*attribute) {
+                if is_text(attr) {
+                    unitless.extend(attr.text()?.split_whitespace().map(|n| n.trim_end_matches(':').to_string()));
+                }
+            }
+        }
+    }
+
+    let mut var_names: Vec<&String> = group.variables.keys().collect();
+    var_names.sort();
+    for name in var_names {
+        let var = &group.variables[name];
+        check_variable(var, group, &scope, &format!("{}{}", path, name), unitless.contains(name), report)?;
+    }
+
+    let mut group_names: Vec<&String> = group.sub_groups.keys().collect();
+    group_names.sort();
+    for name in group_names {
+        if !is_valid_name(name) {
+            report.push(Severity::Warning, path, Violation::InvalidName(name.clone()));
+        }
+        check_group(&group.sub_groups[name], &format!("{}{}/", path, name), &scope, report)?;
+    }
+    Ok(())
+}
+
+fn check_variable(var: &Variable, group: &Group, scope: &[&Group], location: &str,
+                  unitless: bool, report: &mut Report) -> Result<()> {
+    if !is_valid_name(&var.name) {
+        report.push(Severity::Warning, location, Violation::InvalidName(var.name.clone()));
+    }
+    let numeric = var.vartype != NC_CHAR && (NC_BYTE..=NC_UINT64).contains(&var.vartype);
+
+    for name in &TYPED_ATTRIBUTES {
+        if let Some(attr) = var.attributes.get(*name) {
+            if attr.attrtype != var.vartype {
+                report.push(Severity::Error, location, Violation::AttributeType {
+                    name: name.to_string(), expected: var.vartype, found: attr.attrtype });
+            }
+        }
+    }
+
+    let units = text_attribute(&var.attributes, "units", location, report)?;
+    let flags = var.attributes.contains_key("flag_values") || var.attributes.contains_key("flag_masks");
+    if numeric && !unitless && !flags && !var.attributes.contains_key("units") {
+        report.push(Severity::Warning, location, Violation::MissingUnits);
+    }
+
+    let calendar = match text_attribute(&var.attributes, "calendar", location, report)? {
+        Some(name) => match Calendar::from_name(&name) {
+            Ok(calendar) => calendar,
+            Err(_) => {
+                report.push(Severity::Error, location, Violation::InvalidCalendar(name));
+                Calendar::Standard
+            }
+        },
+        None => Calendar::Standard,
+    };
+    if let Some(units) = units {
+        if units.to_lowercase().contains("since") && TimeUnits::parse(&units, calendar).is_err() {
+            report.push(Severity::Error, location, Violation::InvalidTimeUnits(units));
+        }
+    }
+
+    if let Some(standard_name) = text_attribute(&var.attributes, "standard_name", location, report)? {
+        if !is_valid_standard_name(&standard_name) {
+            report.push(Severity::Error, location, Violation::InvalidStandardName(standard_name));
+        }
+    }
+
+    let find_variable = |name: &str| scope.iter().rev().find_map(|g| g.variables.get(name));
+    for attribute in &["coordinates", "bounds", "grid_mapping", "ancillary_variables"] {
+        let names = match text_attribute(&var.attributes, attribute, location, report)? {
+            Some(names) => names,
+            None => continue,
+        };
+        for name in names.split_whitespace() {
+            // `grid_mapping` may also be "mapping: coordinates ..."
+            let name = name.trim_end_matches(':');
+            match find_variable(name) {
+                Some(bounds) if *attribute == "bounds" => check_bounds(var, bounds, location, report),
+                Some(_) => {}
+                None => report.push(Severity::Error, location, Violation::MissingVariable {
+                    attribute: attribute.to_string(), name: name.to_string() }),
+            }
+        }
+    }
+
+    let is_coordinate = group.coordinate(&var.name)?
+        .is_some_and(|c| c.grp_id == var.grp_id && c.id == var.id);
+    if is_coordinate && numeric {
+        let values: Vec<f64> = var.values_decoded()?;
+        if values.iter().any(|v| v.is_nan()) {
+            report.push(Severity::Error, location, Violation::CoordinateMissingValues);
+        } else if !(values.windows(2).all(|w| w[0] < w[1]) || values.windows(2).all(|w| w[0] > w[1])) {
+            report.push(Severity::Error, location, Violation::CoordinateNotMonotonic);
+        }
+    }
+    Ok(())
+}
+
+fn check_bounds(var: &Variable, bounds: &Variable, location: &str, report: &mut Report) {
+    let dimensions: Vec<String> = var.dimensions.iter().map(|d| d.name.clone()).collect();
+    let bounds_dimensions: Vec<String> = bounds.dimensions.iter().map(|d| d.name.clone()).collect();
+    if bounds_dimensions.len() != dimensions.len() + 1 || bounds_dimensions[..dimensions.len()] != dimensions[..] {
+        report.push(Severity::Error, location, Violation::BoundsShape {
+            bounds: bounds.name.clone(), dimensions, bounds_dimensions });
+    }
+}
+
+fn is_text(attr: &Attribute) -> bool {
+    attr.attrtype == NC_CHAR || attr.attrtype == NC_STRING
+}
+
+/// Value of a text attribute, reporting attributes of another type.
+fn text_attribute(attributes: &HashMap<String, Attribute>, name: &str,
+                  location: &str, report: &mut Report) -> Result<Option<String>> {
+    match attributes.get(name) {
+        Some(attr) if is_text(attr) => Ok(Some(attr.text()?)),
+        Some(attr) => {
+            report.push(Severity::Error, location, Violation::AttributeType {
+                name: name.to_string(), expected: NC_CHAR, found: attr.attrtype });
+            Ok(None)
+        }
+        None => Ok(None),
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A standard name, optionally followed by a modifier
+fn is_valid_standard_name(value: &str) -> bool {
+    let mut words = value.split_whitespace();
+    let name_is_valid = words.next().is_some_and(is_valid_name);
+    let modifier_is_valid = words.next().is_none_or(|m| STANDARD_NAME_MODIFIERS.contains(&m));
+    name_is_valid && modifier_is_valid && words.next().is_none()
+}
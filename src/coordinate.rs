@@ -0,0 +1,189 @@
+//! [CF coordinate variables](http://cfconventions.org/Data/cf-conventions/cf-conventions-1.7/cf-conventions.html#coordinate-types):
+//! one dimensional variables named after their dimension, used to select
+//! slices of other variables by coordinate values instead of indices.
+//!
+//! ```no_run
+//! use netcdf::coordinate::Selection;
+//! # let file = netcdf::open("forecast.nc").unwrap();
+//! let temp = file.root.variables.get("temperature").unwrap();
+//! let selection = Selection::new()
+//!     .range("lat", 30., 45.)
+//!     .nearest("lon", 12.3)
+//!     .equal("time", 24.);
+//! let values: ndarray::ArrayD<f32> = temp.array_selected(&file.root, &selection).unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::ffi;
+use ndarray::ArrayD;
+use netcdf_sys::*;
+use dimension::Dimension;
+use error::{Error, Result};
+use group::Group;
+use variable::{init_variable, Numeric, Variable};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Selector {
+    Range(f64, f64),
+    Equal(f64),
+    Nearest(f64),
+}
+
+impl Selector {
+    /// Index range matching the selector in `coordinates`
+    fn indices(self, coordinates: &[f64]) -> Option<(usize, usize)> {
+        match self {
+            Selector::Range(min, max) => {
+                let (min, max) = if min <= max { (min, max) } else { (max, min) };
+                // coordinates are monotonic, the matching values are contiguous
+                let matching = |c: &f64| *c >= min && *c <= max;
+                let first = coordinates.iter().position(matching)?;
+                let last = coordinates.iter().rposition(matching)?;
+                Some((first, last - first + 1))
+            }
+            Selector::Equal(value) => {
+                coordinates.iter().position(|c| *c == value).map(|i| (i, 1))
+            }
+            Selector::Nearest(value) => {
+                if value.is_nan() {
+                    return None;
+                }
+                // an infinite coordinate is at a NaN distance of the same infinite value
+                let distance = |c: f64| if c == value { 0. } else { (c - value).abs() };
+                coordinates.iter()
+                    .enumerate()
+                    .filter(|&(_, c)| !c.is_nan())
+                    .min_by(|&(_, a), &(_, b)| distance(*a).total_cmp(&distance(*b)))
+                    .map(|(i, _)| (i, 1))
+            }
+        }
+    }
+}
+
+/// Selection of a variable slice by coordinate values, dimensions without
+/// selection being read in full.
+///
+/// Coordinate values are unpacked following the CF conventions,
+/// time coordinates can be given with `TimeUnits::encode`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Selection {
+    selectors: Vec<(String, Selector)>,
+}
+
+impl Selection {
+    pub fn new() -> Selection {
+        Selection::default()
+    }
+
+    /// Select the coordinates between `min` and `max` (inclusive) along `dim`.
+    pub fn range(mut self, dim: &str, min: f64, max: f64) -> Selection {
+        self.selectors.push((dim.to_string(), Selector::Range(min, max)));
+        self
+    }
+
+    /// Select the coordinate equal to `value` along `dim`.
+    pub fn equal(mut self, dim: &str, value: f64) -> Selection {
+        self.selectors.push((dim.to_string(), Selector::Equal(value)));
+        self
+    }
+
+    /// Select the coordinate nearest to `value` along `dim`.
+    pub fn nearest(mut self, dim: &str, value: f64) -> Selection {
+        self.selectors.push((dim.to_string(), Selector::Nearest(value)));
+        self
+    }
+}
+
+impl Group {
+    /// Returns the coordinate variable of a dimension: the one dimensional
+    /// variable named after it, in this group or in a parent group up to the
+    /// one defining the dimension. Variables of the parent groups are read
+    /// from the file.
+    pub fn coordinate(&self, dim_name: &str) -> Result<Option<Variable>> {
+        match self.dimensions.get(dim_name) {
+            Some(dim) => find_coordinate(self, dim),
+            None => Ok(None),
+        }
+    }
+}
+
+fn is_coordinate_of(var: &Variable, dim: &Dimension) -> bool {
+    var.name == dim.name && var.dimensions.len() == 1 && var.dimensions[0].id == dim.id
+}
+
+/// Coordinate variable of `dim`, looked up from `group` up to the group defining `dim`.
+fn find_coordinate(group: &Group, dim: &Dimension) -> Result<Option<Variable>> {
+    if let Some(var) = group.variables.get(&dim.name) {
+        if is_coordinate_of(var, dim) {
+            return Ok(Some(var.clone()));
+        }
+    }
+    let name_c: ffi::CString = ffi::CString::new(dim.name.clone()).unwrap();
+    let mut grp_id = group.id;
+    while grp_id != dim.grp_id {
+        let mut parent_id = 0i32;
+        let mut varid = 0i32;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            let err_parent = nc_inq_grp_parent(grp_id, &mut parent_id);
+            if err_parent != NC_NOERR {
+                // NC_ENOGRP: `dim` is not defined by a parent group
+                return Err(Error::netcdf(err_parent, "nc_inq_grp_parent", dim.name.clone()));
+            }
+            err = nc_inq_varid(parent_id, name_c.as_ptr(), &mut varid);
+        }
+        grp_id = parent_id;
+        if err == NC_ENOTVAR {
+            continue;
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_varid", dim.name.clone()));
+        }
+        let mut vars = HashMap::new();
+        init_variable(&mut vars, grp_id, &HashMap::new(), varid)?;
+        if let Some(var) = vars.remove(&dim.name).filter(|v| is_coordinate_of(v, dim)) {
+            return Ok(Some(var));
+        }
+    }
+    Ok(None)
+}
+
+impl Variable {
+    /// Translates `selection` into the `indices` and `slice_len` of a slice,
+    /// using the coordinate variables visible from `group`, the group holding this variable.
+    ///
+    /// Equality and nearest neighbour selections keep their dimension, of length 1.
+    pub fn selection_slice(&self, group: &Group, selection: &Selection) -> Result<(Vec<usize>, Vec<usize>)> {
+        if group.id != self.grp_id {
+            return Err(Error::NotInGroup { variable: self.name.clone(), group: group.name.clone() });
+        }
+        let mut indices = vec![0; self.dimensions.len()];
+        let mut slice_len: Vec<usize> = self.dimensions.iter().map(|d| d.len() as usize).collect();
+        for &(ref dim_name, selector) in &selection.selectors {
+            let i_dim = self.dimensions.iter()
+                .position(|d| d.name == *dim_name)
+                .ok_or_else(|| Error::DimensionNotFound(dim_name.clone()))?;
+            let coordinate = find_coordinate(group, &self.dimensions[i_dim])?
+                .ok_or_else(|| Error::VariableNotFound(dim_name.clone()))?;
+            let coordinates: Vec<f64> = coordinate.values_decoded()?;
+            let (start, len) = selector.indices(&coordinates)
+                .ok_or_else(|| Error::EmptySelection(dim_name.clone()))?;
+            indices[i_dim] = start;
+            slice_len[i_dim] = len;
+        }
+        Ok((indices, slice_len))
+    }
+
+    /// Fetchs the values selected by coordinates, see `selection_slice`.
+    pub fn values_selected<T: Numeric>(&self, group: &Group, selection: &Selection) -> Result<Vec<T>> {
+        let (indices, slice_len) = self.selection_slice(group, selection)?;
+        self.values_at(&indices, &slice_len)
+    }
+
+    /// Fetchs the values selected by coordinates as a ndarray, see `selection_slice`.
+    pub fn array_selected<T: Numeric>(&self, group: &Group, selection: &Selection) -> Result<ArrayD<T>> {
+        let (indices, slice_len) = self.selection_slice(group, selection)?;
+        self.array_at(&indices, &slice_len)
+    }
+}
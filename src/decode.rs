@@ -0,0 +1,222 @@
+//! Decoding of [CF packed data](http://cfconventions.org/Data/cf-conventions/cf-conventions-1.7/cf-conventions.html#packed-data):
+//! values stored as small integers with `scale_factor` and `add_offset`
+//! attributes, and missing values flagged by `_FillValue` (or the default
+//! fill value of the type), `missing_value`, `valid_min`, `valid_max` or `valid_range`.
+//!
+//! ```no_run
+//! # let file = netcdf::open("swath.nc").unwrap();
+//! let var = file.root.variables.get("radiance").unwrap();
+//! // i16 values unpacked to f64, missing values are NaN
+//! let radiance: Vec<f64> = var.values_decoded().unwrap();
+//! // raw values, with a mask set for missing values
+//! let (counts, mask) = var.as_array_masked::<i16>().unwrap();
+//! ```
+
+use ndarray::ArrayD;
+use netcdf_sys::*;
+use error::{Error, Result};
+use variable::{Numeric, Variable};
+
+/// Floating point types packed values are decoded to.
+pub trait Float: Numeric + Copy {
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn nan() -> Self;
+}
+
+impl Float for f32 {
+    fn from_f64(value: f64) -> f32 { value as f32 }
+    fn to_f64(self) -> f64 { f64::from(self) }
+    fn nan() -> f32 { f32::NAN }
+}
+
+impl Float for f64 {
+    fn from_f64(value: f64) -> f64 { value }
+    fn to_f64(self) -> f64 { self }
+    fn nan() -> f64 { f64::NAN }
+}
+
+/// Values of a variable flagged as missing by its attributes,
+/// in the type of the stored (packed) values.
+pub(crate) struct MissingValues<T> {
+    fill_value: Option<T>,
+    missing_values: Vec<T>,
+    valid_min: Option<T>,
+    valid_max: Option<T>,
+}
+
+impl<T: Numeric + PartialOrd + Copy> MissingValues<T> {
+    pub(crate) fn from_variable(var: &Variable) -> Result<MissingValues<T>> {
+        let attr_values = |name: &str| -> Result<Vec<T>> {
+            match var.attributes.get(name) {
+                Some(attr) => attr.values::<T>(),
+                None => Ok(Vec::new()),
+            }
+        };
+        let fill_value = match var.attributes.get("_FillValue") {
+            Some(attr) => attr.values::<T>()?.first().cloned(),
+            // unwritten values hold the default fill value of the type
+            None => T::fill_value_from_variable(var)?,
+        };
+        let missing_values = attr_values("missing_value")?;
+        // `valid_range` takes precedence over `valid_min` and `valid_max`
+        let valid_range = attr_values("valid_range")?;
+        let (valid_min, valid_max) = if valid_range.len() == 2 {
+            (Some(valid_range[0]), Some(valid_range[1]))
+        } else {
+            (attr_values("valid_min")?.first().cloned(), attr_values("valid_max")?.first().cloned())
+        };
+        Ok(MissingValues { fill_value, missing_values, valid_min, valid_max })
+    }
+
+    pub(crate) fn is_missing(&self, value: T) -> bool {
+        // NaN values (not comparable to themselves) are never valid
+        value.partial_cmp(&value).is_none()
+            || self.fill_value == Some(value)
+            || self.missing_values.contains(&value)
+            || self.valid_min.is_some_and(|min| value < min)
+            || self.valid_max.is_some_and(|max| value > max)
+    }
+
+    /// Value written in place of missing values: the `_FillValue`,
+    /// or the first `missing_value`.
+    fn fill(&self) -> Option<T> {
+        self.fill_value.or_else(|| self.missing_values.first().cloned())
+    }
+}
+
+/// `scale_factor` and `add_offset` of a variable.
+struct Packing {
+    scale_factor: f64,
+    add_offset: f64,
+}
+
+impl Packing {
+    fn from_variable(var: &Variable) -> Result<Packing> {
+        let attr_value = |name: &str, default: f64| -> Result<f64> {
+            match var.attributes.get(name) {
+                Some(attr) => Ok(attr.values::<f64>()?.first().cloned().unwrap_or(default)),
+                None => Ok(default),
+            }
+        };
+        Ok(Packing {
+            scale_factor: attr_value("scale_factor", 1.)?,
+            add_offset: attr_value("add_offset", 0.)?,
+        })
+    }
+}
+
+fn is_integer_type(nctype: i32) -> bool {
+    matches!(nctype, NC_BYTE | NC_UBYTE | NC_SHORT | NC_USHORT |
+                     NC_INT | NC_UINT | NC_INT64 | NC_UINT64)
+}
+
+impl Variable {
+    /// Unpack `raw` values, `None` marking missing values.
+    fn decode<T: Float>(&self, raw: Vec<f64>) -> Result<Vec<Option<T>>> {
+        let missing = MissingValues::<f64>::from_variable(self)?;
+        let packing = Packing::from_variable(self)?;
+        Ok(raw.into_iter()
+            .map(|v| {
+                if missing.is_missing(v) {
+                    None
+                } else {
+                    Some(T::from_f64(v * packing.scale_factor + packing.add_offset))
+                }
+            })
+            .collect())
+    }
+
+    /// Fetchs the variable values unpacked following the CF conventions
+    /// (`value * scale_factor + add_offset`), missing values being NaN.
+    ///
+    /// Missing values are found in the packed values using the `_FillValue`,
+    /// `missing_value`, `valid_min`, `valid_max` and `valid_range` attributes.
+    pub fn values_decoded<T: Float>(&self) -> Result<Vec<T>> {
+        Ok(self.values_decoded_option()?
+            .into_iter()
+            .map(|v| v.unwrap_or_else(T::nan))
+            .collect())
+    }
+
+    /// Same as `values_decoded`, missing values being `None`.
+    pub fn values_decoded_option<T: Float>(&self) -> Result<Vec<Option<T>>> {
+        let raw: Vec<f64> = self.values()?;
+        self.decode(raw)
+    }
+
+    /// Fetchs a slice of the variable unpacked, see `values_decoded`.
+    pub fn values_at_decoded<T: Float>(&self, indices: &[usize], slice_len: &[usize]) -> Result<Vec<T>> {
+        let raw: Vec<f64> = self.values_at(indices, slice_len)?;
+        Ok(self.decode(raw)?
+            .into_iter()
+            .map(|v| v.unwrap_or_else(T::nan))
+            .collect())
+    }
+
+    /// Fetchs the variable unpacked as a ndarray, see `values_decoded`.
+    pub fn as_array_decoded<T: Float>(&self) -> Result<ArrayD<T>> {
+        let dims: Vec<usize> = self.dimensions.iter().map(|d| d.len() as usize).collect();
+        let values = self.values_decoded()?;
+        Ok(ArrayD::<T>::from_shape_vec(dims, values)?)
+    }
+
+    /// Fetchs the variable as a ndarray, along with a mask set for missing values.
+    ///
+    /// Missing values are found using the `_FillValue` (or the default fill value
+    /// of the variable type), `missing_value`, `valid_min`, `valid_max` and
+    /// `valid_range` attributes.
+    pub fn as_array_masked<T>(&self) -> Result<(ArrayD<T>, ArrayD<bool>)>
+        where T: Numeric + PartialOrd + Copy
+    {
+        let dims: Vec<usize> = self.dimensions.iter().map(|d| d.len() as usize).collect();
+        self.array_at_masked(&vec![0; dims.len()], &dims)
+    }
+
+    /// Fetchs a slice of the variable as a ndarray, along with a mask set
+    /// for missing values, see `as_array_masked`.
+    pub fn array_at_masked<T>(&self, indices: &[usize], slice_len: &[usize]) -> Result<(ArrayD<T>, ArrayD<bool>)>
+        where T: Numeric + PartialOrd + Copy
+    {
+        let missing = MissingValues::<T>::from_variable(self)?;
+        let values = self.array_at::<T>(indices, slice_len)?;
+        let mask = values.map(|v| missing.is_missing(*v));
+        Ok((values, mask))
+    }
+
+    /// Fetchs the variable as a ndarray, missing values being `None`,
+    /// see `as_array_masked`.
+    pub fn as_array_option<T>(&self) -> Result<ArrayD<Option<T>>>
+        where T: Numeric + PartialOrd + Copy
+    {
+        let missing = MissingValues::<T>::from_variable(self)?;
+        let values = self.as_array::<T>()?;
+        Ok(values.map(|v| if missing.is_missing(*v) { None } else { Some(*v) }))
+    }
+
+    /// Pack `values` following the CF conventions (`(value - add_offset) / scale_factor`,
+    /// rounded for integer variables) and put them at `indices`.
+    ///
+    /// NaN values are written as the `_FillValue` (or the first `missing_value`).
+    pub fn put_values_encoded<T: Float>(&mut self, values: &[T], indices: &[usize], slice_len: &[usize]) -> Result<()> {
+        let missing = MissingValues::<f64>::from_variable(self)?;
+        let packing = Packing::from_variable(self)?;
+        let integer = is_integer_type(self.vartype);
+        let mut encoded: Vec<f64> = Vec::with_capacity(values.len());
+        for value in values {
+            let value = value.to_f64();
+            if value.is_nan() {
+                match missing.fill() {
+                    Some(fill) => encoded.push(fill),
+                    // a float variable can hold NaN itself
+                    None if !integer => encoded.push(value),
+                    None => return Err(Error::NoFillValue(self.name.clone())),
+                }
+                continue;
+            }
+            let packed = (value - packing.add_offset) / packing.scale_factor;
+            encoded.push(if integer { packed.round() } else { packed });
+        }
+        f64::put_values_at(self, indices, slice_len, &encoded)
+    }
+}
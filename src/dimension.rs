@@ -1,40 +1,148 @@
 use std::ffi;
+use std::ptr;
 use std::collections::HashMap;
 use netcdf_sys::*;
 use string_from_c_str;
+use error::{Error, Result};
 
 #[derive(Clone)]
 pub struct Dimension {
     pub name : String,
-    pub len: u64,
+    /// length when defined or read, see `len()`
+    pub(crate) cached_len: u64,
+    /// the netcdf dimid, unique within the file
     pub id: i32,
+    /// id of the group which defined this dimension
+    /// (it is visible in this group and all its child groups)
+    pub grp_id: i32,
+    pub(crate) unlimited: bool,
 }
 
-pub fn init_dimensions(dims: &mut HashMap<String, Dimension>, grp_id: i32) {
-    // determine number of dims
+impl Dimension {
+    /// Returns `true` for unlimited (record) dimensions, which grow as data is written along them.
+    pub fn is_unlimited(&self) -> bool {
+        self.unlimited
+    }
+
+    /// Current length; for unlimited dimensions, the number of records written
+    /// so far, read from the file as any variable may have extended it.
+    pub fn len(&self) -> u64 {
+        if !self.unlimited {
+            return self.cached_len;
+        }
+        let mut len: u64 = 0;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_inq_dimlen(self.grp_id, self.id, &mut len);
+        }
+        // e.g. the file was closed, the last known length is all we have
+        if err != NC_NOERR {
+            return self.cached_len;
+        }
+        len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// dimids defined in the group `grp_id` (excluding its parents)
+fn group_dimids(grp_id: i32) -> Result<Vec<i32>> {
     let mut ndims = 0i32;
+    let mut dimids: Vec<i32>;
+    unsafe {
+        let _g = libnetcdf_lock.lock().unwrap();
+        let mut err = nc_inq_dimids(grp_id, &mut ndims, ptr::null_mut(), 0);
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_dimids", format!("ncid {}", grp_id)));
+        }
+        dimids = vec![0i32; ndims as usize];
+        err = nc_inq_dimids(grp_id, &mut ndims, dimids.as_mut_ptr(), 0);
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_dimids", format!("ncid {}", grp_id)));
+        }
+    }
+    Ok(dimids)
+}
+
+/// unlimited dimids defined in the group `grp_id`
+fn unlimited_dimids(grp_id: i32) -> Result<Vec<i32>> {
+    let mut nunlimdims = 0i32;
+    let mut unlimdimids: Vec<i32>;
     unsafe {
         let _g = libnetcdf_lock.lock().unwrap();
-        let err = nc_inq_ndims(grp_id, &mut ndims);
-        assert_eq!(err, NC_NOERR);
+        let mut err = nc_inq_unlimdims(grp_id, &mut nunlimdims, ptr::null_mut());
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_unlimdims", format!("ncid {}", grp_id)));
+        }
+        unlimdimids = vec![0i32; nunlimdims as usize];
+        err = nc_inq_unlimdims(grp_id, &mut nunlimdims, unlimdimids.as_mut_ptr());
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_unlimdims", format!("ncid {}", grp_id)));
+        }
     }
+    Ok(unlimdimids)
+}
 
-    // read each dim name and length
-    for i_dim in 0..ndims {
-        let mut buf_vec = vec![0i8; (NC_MAX_NAME + 1) as usize];
-        let mut dimlen : u64 = 0u64;
-        let c_str: &ffi::CStr;
+/// Read name and length of the dimension `dimid` defined in the group `grp_id`
+fn read_dimension(grp_id: i32, dimid: i32, unlimited: bool) -> Result<Dimension> {
+    let mut buf_vec = vec![0i8; (NC_MAX_NAME + 1) as usize];
+    let mut dimlen : u64 = 0u64;
+    let c_str: &ffi::CStr;
+    unsafe {
+        let _g = libnetcdf_lock.lock().unwrap();
+        let buf_ptr : *mut i8 = buf_vec.as_mut_ptr();
+        let err = nc_inq_dimname(grp_id, dimid, buf_ptr);
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_dimname", format!("dimid {}", dimid)));
+        }
+        c_str = ffi::CStr::from_ptr(buf_ptr);
+        let err = nc_inq_dimlen(grp_id, dimid, &mut dimlen);
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_dimlen", c_str.to_string_lossy().into_owned()));
+        }
+    }
+    Ok(Dimension {
+        name: string_from_c_str(c_str)?,
+        cached_len: dimlen,
+        id: dimid,
+        grp_id,
+        unlimited,
+    })
+}
+
+/// Find the dimension `dimid` visible from the group `grp_id`,
+/// it may be defined by `grp_id` itself or by any of its ancestors.
+pub(crate) fn find_dimension(grp_id: i32, dimid: i32) -> Result<Dimension> {
+    let mut cur_grp_id = grp_id;
+    loop {
+        if group_dimids(cur_grp_id)?.contains(&dimid) {
+            let unlimited = unlimited_dimids(cur_grp_id)?.contains(&dimid);
+            return read_dimension(cur_grp_id, dimid, unlimited);
+        }
+        let mut parent_id = 0i32;
+        let err: i32;
         unsafe {
             let _g = libnetcdf_lock.lock().unwrap();
-            let buf_ptr : *mut i8 = buf_vec.as_mut_ptr();
-            let err = nc_inq_dim(grp_id, i_dim, buf_ptr, &mut dimlen);
-            assert_eq!(err, NC_NOERR);
-            c_str = ffi::CStr::from_ptr(buf_ptr);
+            err = nc_inq_grp_parent(cur_grp_id, &mut parent_id);
         }
-        let str_buf: String = string_from_c_str(c_str);
-        dims.insert(str_buf.clone(),
-                      Dimension{name: str_buf.clone(),
-                          len: dimlen,
-                          id: i_dim});
+        if err != NC_NOERR {
+            // NC_ENOGRP: reached the root group without finding `dimid`
+            return Err(Error::netcdf(err, "nc_inq_grp_parent", format!("dimid {}", dimid)));
+        }
+        cur_grp_id = parent_id;
+    }
+}
+
+/// Add the dimensions defined in the group `grp_id` to `dims`,
+/// replacing any inherited dimension they shadow.
+pub fn init_dimensions(dims: &mut HashMap<String, Dimension>, grp_id: i32) -> Result<()> {
+    let unlimdimids = unlimited_dimids(grp_id)?;
+    for dimid in group_dimids(grp_id)? {
+        let dim = read_dimension(grp_id, dimid, unlimdimids.contains(&dimid))?;
+        dims.insert(dim.name.clone(), dim);
     }
+    Ok(())
 }
@@ -0,0 +1,217 @@
+use std::fmt;
+use std::error;
+use std::result;
+use ndarray::ShapeError;
+use NC_ERRORS;
+use time::Calendar;
+
+/// Result type used throughout the crate.
+pub type Result<T> = result::Result<T, Error>;
+
+/// Errors returned by the netcdf crate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// A libnetcdf call failed.
+    Netcdf {
+        /// The raw status code returned by libnetcdf (`NC_ENOTVAR`, `NC_EBADTYPE`, ...)
+        code: i32,
+        /// The libnetcdf function that failed
+        operation: &'static str,
+        /// The file path, variable, attribute, ... the call was made on
+        context: String,
+    },
+    /// The requested type does not match the stored type and casting was not allowed.
+    TypeMismatch { expected: i32, found: i32 },
+    /// Wrong number of indices for the variable dimensions.
+    IndexLen { expected: usize, found: usize },
+    /// Wrong number of slice lengths for the variable dimensions.
+    SliceLen { expected: usize, found: usize },
+    /// An index is beyond the length of its dimension.
+    IndexOutOfRange { dim: usize, index: usize, len: u64 },
+    /// An index of a slice description (`s![..]`) is beyond the length of its dimension,
+    /// negative indices count from the end of the dimension.
+    SliceIndexOutOfRange { dim: usize, index: isize, len: u64 },
+    /// A slice extends beyond the length of its dimension.
+    SliceOutOfRange { dim: usize, start: usize, count: usize, len: u64 },
+    /// Wrong number of `imap` elements for the slice dimensions.
+    ImapLen { expected: usize, found: usize },
+    /// Wrong number of strides for the variable dimensions.
+    StrideLen { expected: usize, found: usize },
+    /// A strided slice extends beyond the length of its dimension.
+    StridedSliceOutOfRange { dim: usize, start: usize, count: usize, stride: usize, len: u64 },
+    /// A stride is zero along one of the dimensions.
+    ZeroStride { dim: usize },
+    /// A slice has a zero length along one of its dimensions.
+    ZeroSlice { dim: usize },
+    /// The number of values does not match the requested slice.
+    ValuesLen { expected: usize, found: usize },
+    /// A caller provided buffer is too small.
+    BufferTooSmall { needed: usize, capacity: usize },
+    /// Wrong number of chunk sizes for the variable dimensions.
+    ChunkLen { expected: usize, found: usize },
+    /// A deflate level outside of 0 to 9.
+    DeflateLevel(i32),
+    /// A chunk size of zero, or bigger than its fixed dimension.
+    ChunkSize { dim: usize, size: usize, len: u64 },
+    /// A storage option (chunking, filters, endianness) used on a file which isn't netCDF-4.
+    NotNetcdf4(&'static str),
+    /// Unlimited dimensions and filters need chunked storage.
+    NotChunked(String),
+    /// A string does not fit in the string length dimension of a char variable.
+    StringTooLong { len: usize, max: usize },
+    /// A string contains a NUL byte, which libnetcdf can't store.
+    InteriorNul(String),
+    /// A name or text read from the file is not valid UTF-8, shown with the
+    /// invalid sequences replaced.
+    InvalidUtf8(String),
+    /// Multi-value attribute read as a scalar.
+    AttributeNotScalar { name: String, len: u64 },
+    /// The netCDF type is not supported (e.g. user defined types).
+    UnsupportedType { name: String, nctype: i32 },
+    /// An existing dimension doesn't match the length of the data written along it.
+    DimensionLen { name: String, len: u64, found: usize },
+    /// Missing values can't be written, the variable has neither `_FillValue` nor `missing_value`.
+    NoFillValue(String),
+    /// No dimension with this name is visible.
+    DimensionNotFound(String),
+    /// No variable with this name.
+    VariableNotFound(String),
+    /// No sub-group with this name.
+    GroupNotFound(String),
+    /// The variable is not defined in the group it was used with.
+    NotInGroup { variable: String, group: String },
+    /// No attribute with this name.
+    AttributeNotFound(String),
+    /// No coordinate value of this dimension matches the selection.
+    EmptySelection(String),
+    /// CF time `units` or `calendar` that could not be parsed.
+    TimeUnits(String),
+    /// A date that does not exist in its calendar.
+    InvalidDatetime(String),
+    /// A date can't be converted to the calendar of a variable.
+    CalendarMismatch { expected: Calendar, found: Calendar },
+    /// Data could not be shaped as requested.
+    Shape(ShapeError),
+}
+
+impl Error {
+    /// Build an `Error::Netcdf` from a libnetcdf status code.
+    pub fn netcdf<S: Into<String>>(code: i32, operation: &'static str, context: S) -> Error {
+        Error::Netcdf {
+            code,
+            operation,
+            context: context.into(),
+        }
+    }
+
+    /// Returns the libnetcdf status code, if the error comes from libnetcdf.
+    pub fn code(&self) -> Option<i32> {
+        match *self {
+            Error::Netcdf { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Netcdf { code, operation, ref context } => {
+                let msg = NC_ERRORS.get(&code).map(|s| s.as_str()).unwrap_or("Unknown Error");
+                write!(f, "{} ({}) on '{}': {}", operation, code, context, msg)
+            }
+            Error::TypeMismatch { expected, found } => write!(
+                f, "Types are not equivalent and cast==false (requested {}, found {})",
+                expected, found),
+            Error::IndexLen { expected, found } => write!(
+                f, "`indices` must has the same length as the variable dimensions ({} != {})",
+                found, expected),
+            Error::SliceLen { expected, found } => write!(
+                f, "`slice` must has the same length as the variable dimensions ({} != {})",
+                found, expected),
+            Error::IndexOutOfRange { dim, index, len } => write!(
+                f, "requested index {} is bigger than the dimension {} length ({})",
+                index, dim, len),
+            Error::SliceIndexOutOfRange { dim, index, len } => write!(
+                f, "slice index {} is out of range for the dimension {} of length {}",
+                index, dim, len),
+            Error::SliceOutOfRange { dim, start, count, len } => write!(
+                f, "requested slice {}..{} is bigger than the dimension {} length ({})",
+                start, start + count, dim, len),
+            Error::ImapLen { expected, found } => write!(
+                f, "`imap` must has the same length as the variable dimensions ({} != {})",
+                found, expected),
+            Error::StrideLen { expected, found } => write!(
+                f, "`stride` must has the same length as the variable dimensions ({} != {})",
+                found, expected),
+            Error::StridedSliceOutOfRange { dim, start, count, stride, len } => write!(
+                f, "requested slice {}..{} with stride {} is bigger than the dimension {} length ({})",
+                start, start + (count - 1) * stride + 1, stride, dim, len),
+            Error::ZeroStride { dim } => write!(
+                f, "Each stride element must be superior than 0 (dimension {})", dim),
+            Error::ZeroSlice { dim } => write!(
+                f, "Each slice element must be superior than 0 (dimension {})", dim),
+            Error::ValuesLen { expected, found } => write!(
+                f, "number of element in `values` ({}) doesn't match `slice_len` ({})",
+                found, expected),
+            Error::BufferTooSmall { needed, capacity } => write!(
+                f, "Buffer is not big enough. (size {} needed, capacity is {})",
+                needed, capacity),
+            Error::ChunkLen { expected, found } => write!(
+                f, "chunk sizes must have the same length as the variable dimensions ({} != {})",
+                found, expected),
+            Error::DeflateLevel(level) => write!(
+                f, "deflate level {} is out of the 0 to 9 range", level),
+            Error::ChunkSize { dim, size, len } => write!(
+                f, "chunk size {} of dimension {} must be between 1 and the dimension length ({})",
+                size, dim, len),
+            Error::NotNetcdf4(option) => write!(
+                f, "{} is only supported by netCDF-4 files", option),
+            Error::NotChunked(ref what) => write!(f, "{} requires chunked storage", what),
+            Error::StringTooLong { len, max } => write!(
+                f, "string of {} bytes doesn't fit in the string length dimension ({})",
+                len, max),
+            Error::InteriorNul(ref value) => write!(
+                f, "string {:?} contains a NUL byte", value),
+            Error::InvalidUtf8(ref value) => write!(
+                f, "string {:?} is not valid UTF-8", value),
+            Error::AttributeNotScalar { ref name, len } => write!(
+                f, "Attribute '{}' holds {} values, expected a single one", name, len),
+            Error::UnsupportedType { ref name, nctype } => write!(
+                f, "'{}' has the unsupported type {}", name, nctype),
+            Error::DimensionLen { ref name, len, found } => write!(
+                f, "dimension '{}' has a length of {}, found {} values along it",
+                name, len, found),
+            Error::NoFillValue(ref name) => write!(
+                f, "Variable '{}' has no _FillValue or missing_value for missing values", name),
+            Error::DimensionNotFound(ref name) => write!(f, "Invalid dimension name '{}'", name),
+            Error::VariableNotFound(ref name) => write!(f, "Variable '{}' not found", name),
+            Error::GroupNotFound(ref name) => write!(f, "Group '{}' not found", name),
+            Error::NotInGroup { ref variable, ref group } => write!(
+                f, "Variable '{}' is not defined in the group '{}'", variable, group),
+            Error::AttributeNotFound(ref name) => write!(f, "Attribute '{}' not found", name),
+            Error::EmptySelection(ref dim) => write!(
+                f, "No '{}' coordinate value matches the selection", dim),
+            Error::TimeUnits(ref msg) => write!(f, "Invalid CF time units: {}", msg),
+            Error::InvalidDatetime(ref date) => write!(f, "Invalid date {}", date),
+            Error::CalendarMismatch { expected, found } => write!(
+                f, "Can't convert a {} date to the {} calendar", found.name(), expected.name()),
+            Error::Shape(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Shape(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ShapeError> for Error {
+    fn from(e: ShapeError) -> Error {
+        Error::Shape(e)
+    }
+}
@@ -2,17 +2,21 @@ use std::ffi;
 use std::path;
 use std::collections::HashMap;
 use netcdf_sys::*;
+use libc;
 use group::{init_group, Group};
-use NC_ERRORS;
+use variable::{Chunking, Compression, Endianness, Variable, VariableOptions};
+use error::{Error, Result};
+use data_mode;
 
 pub struct File {
     pub id: i32,
     pub name: String,
     pub root: Group,
+    closed: bool,
 }
 
 /// Open a netCDF file in read only mode.
-pub fn open(file: &str) -> Result<File, String> {
+pub fn open(file: &str) -> Result<File> {
     let data_path = path::Path::new(file);
     let f = ffi::CString::new(data_path.to_str().unwrap()).unwrap();
     let mut ncid : i32 = -999999i32;
@@ -22,27 +26,17 @@ pub fn open(file: &str) -> Result<File, String> {
         err = nc_open(f.as_ptr(), NC_NOWRITE, &mut ncid);
     }
     if err != NC_NOERR {
-        return Err(NC_ERRORS.get(&err).unwrap().clone());
+        return Err(Error::netcdf(err, "nc_open", file));
     }
-    let mut root = Group {
-            name: "root".to_string(),
-            id: ncid,
-            variables: HashMap::new(),
-            attributes: HashMap::new(),
-            dimensions: HashMap::new(),
-            sub_groups: HashMap::new(),
-        };
-    init_group(&mut root);
-    Ok(File {
-        id: ncid, 
-        name: file.to_string(),
-        root: root,
-    })
+    let mut file = File::new(ncid, file);
+    // on failure `file` is dropped here, which closes `ncid`
+    init_group(&mut file.root)?;
+    Ok(file)
 }
 
 /// Open a netCDF file in append mode (read/write).
 /// The file must already exist.
-pub fn append(file: &str) -> Result<File, String> {
+pub fn append(file: &str) -> Result<File> {
     let data_path = path::Path::new(file);
     let f = ffi::CString::new(data_path.to_str().unwrap()).unwrap();
     let mut ncid : i32 = -999999i32;
@@ -52,66 +46,463 @@ pub fn append(file: &str) -> Result<File, String> {
         err = nc_open(f.as_ptr(), NC_WRITE, &mut ncid);
     }
     if err != NC_NOERR {
-        return Err(NC_ERRORS.get(&err).unwrap().clone());
+        return Err(Error::netcdf(err, "nc_open", file));
+    }
+    let mut file = File::new(ncid, file);
+    init_group(&mut file.root)?;
+    Ok(file)
+}
+
+/// On-disk format of a netCDF file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// netCDF classic (CDF-1)
+    Classic,
+    /// netCDF 64-bit offset (CDF-2)
+    Offset64,
+    /// netCDF 64-bit data (CDF-5)
+    Cdf5,
+    /// netCDF-4 (HDF5 based)
+    Netcdf4,
+    /// netCDF-4 restricted to the classic data model
+    Netcdf4Classic,
+}
+
+impl Format {
+    /// `nc_create` mode flags selecting this format
+    fn cmode(self) -> i32 {
+        match self {
+            Format::Classic => 0,
+            Format::Offset64 => NC_64BIT_OFFSET,
+            Format::Cdf5 => NC_64BIT_DATA,
+            Format::Netcdf4 => NC_NETCDF4,
+            Format::Netcdf4Classic => NC_NETCDF4 | NC_CLASSIC_MODEL,
+        }
+    }
+
+    /// `true` for the HDF5 based formats
+    fn is_netcdf4(self) -> bool {
+        matches!(self, Format::Netcdf4 | Format::Netcdf4Classic)
+    }
+
+    /// Format from a `nc_inq_format` value
+    fn from_nc_format(format: i32) -> Option<Format> {
+        match format {
+            NC_FORMAT_CLASSIC => Some(Format::Classic),
+            NC_FORMAT_64BIT_OFFSET => Some(Format::Offset64),
+            NC_FORMAT_CDF5 => Some(Format::Cdf5),
+            NC_FORMAT_NETCDF4 => Some(Format::Netcdf4),
+            NC_FORMAT_NETCDF4_CLASSIC => Some(Format::Netcdf4Classic),
+            _ => None,
+        }
+    }
+}
+
+/// Options used by `create_with`.
+///
+/// ```
+/// # let f = netcdf::test_file_new("create_with_doc.nc");
+/// use netcdf::file::{Format, Options};
+/// let file = netcdf::create_with(&f, Options::new().format(Format::Offset64)).unwrap();
+/// assert_eq!(file.format().unwrap(), Format::Offset64);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    format: Format,
+    noclobber: bool,
+    share: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            format: Format::Netcdf4,
+            noclobber: false,
+            share: false,
+        }
+    }
+}
+
+impl Options {
+    /// Default options: netCDF-4 format, overwriting any existing file.
+    pub fn new() -> Options {
+        Options::default()
+    }
+
+    /// Select the on-disk format.
+    pub fn format(mut self, format: Format) -> Options {
+        self.format = format;
+        self
+    }
+
+    /// Fail instead of overwriting an existing file (`NC_NOCLOBBER`).
+    pub fn noclobber(mut self, noclobber: bool) -> Options {
+        self.noclobber = noclobber;
+        self
+    }
+
+    /// Disable buffering, for files shared between processes (`NC_SHARE`).
+    /// Only meaningful for classic formats.
+    pub fn share(mut self, share: bool) -> Options {
+        self.share = share;
+        self
+    }
+
+    fn cmode(&self) -> i32 {
+        let mut cmode = self.format.cmode();
+        if self.noclobber {
+            cmode |= NC_NOCLOBBER;
+        }
+        if self.share {
+            cmode |= NC_SHARE;
+        }
+        cmode
     }
-    let mut root = Group {
-            name: "root".to_string(),
-            id: ncid,
-            variables: HashMap::new(),
-            attributes: HashMap::new(),
-            dimensions: HashMap::new(),
-            sub_groups: HashMap::new(),
-        };
-    init_group(&mut root);
-    Ok(File {
-        id: ncid, 
-        name: file.to_string(),
-        root: root,
-    })
 }
 
 /// Open a netCDF file in creation mode (write only).
-pub fn create(file: &str) -> Result<File, String> {
+/// The file is created in the netCDF-4 format, see `create_with` for other formats.
+pub fn create(file: &str) -> Result<File> {
+    create_with(file, Options::default())
+}
+
+/// Open a netCDF file in creation mode (write only), with the given `Options`.
+///
+/// Files of the classic formats are switched between define and data mode
+/// as needed, definitions and writes can be mixed in any order.
+pub fn create_with(file: &str, options: Options) -> Result<File> {
     let data_path = path::Path::new(file);
     let f = ffi::CString::new(data_path.to_str().unwrap()).unwrap();
     let mut ncid : i32 = -999999i32;
     let err : i32;
     unsafe {
         let _g = libnetcdf_lock.lock().unwrap();
-        err = nc_create(f.as_ptr(), NC_NETCDF4, &mut ncid);
+        err = nc_create(f.as_ptr(), options.cmode(), &mut ncid);
     }
     if err != NC_NOERR {
-        return Err(NC_ERRORS.get(&err).unwrap().clone());
+        return Err(Error::netcdf(err, "nc_create", file));
     }
-    let root = Group {
-            name: "root".to_string(),
-            id: ncid,
-            variables: HashMap::new(),
-            attributes: HashMap::new(),
-            dimensions: HashMap::new(),
-            sub_groups: HashMap::new(),
+    Ok(File::new(ncid, file))
+}
+
+/// Options used by `copy`.
+///
+/// ```no_run
+/// use netcdf::file::{CopyOptions, Format};
+/// let input = netcdf::open("swath.nc").unwrap();
+/// // recompress the variables into a netCDF-4 file
+/// let options = CopyOptions::new().format(Format::Netcdf4).deflate(4).shuffle(true);
+/// netcdf::copy(&input, "swath_deflated.nc", options).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CopyOptions {
+    format: Option<Format>,
+    deflate_level: Option<i32>,
+    shuffle: bool,
+    buffer_len: usize,
+}
+
+impl Default for CopyOptions {
+    fn default() -> CopyOptions {
+        CopyOptions {
+            format: None,
+            deflate_level: None,
+            shuffle: false,
+            buffer_len: 1 << 20,
+        }
+    }
+}
+
+impl CopyOptions {
+    /// Default options: same format and storage options as the input file.
+    pub fn new() -> CopyOptions {
+        CopyOptions::default()
+    }
+
+    /// Select the on-disk format of the copy.
+    pub fn format(mut self, format: Format) -> CopyOptions {
+        self.format = Some(format);
+        self
+    }
+
+    /// Compress all variables with deflate at `level` from 1 to 9,
+    /// 0 removing the compression (netCDF-4 output only).
+    pub fn deflate(mut self, level: i32) -> CopyOptions {
+        self.deflate_level = Some(level);
+        self
+    }
+
+    /// Apply the shuffle filter to the variables compressed with `deflate`.
+    pub fn shuffle(mut self, shuffle: bool) -> CopyOptions {
+        self.shuffle = shuffle;
+        self
+    }
+
+    /// Maximum number of values read at once, at least one slice
+    /// along the first dimension of a variable being read.
+    pub fn buffer_len(mut self, buffer_len: usize) -> CopyOptions {
+        self.buffer_len = buffer_len;
+        self
+    }
+}
+
+/// Copy a whole file, like `nccopy`: dimensions, groups, variables and attributes
+/// are recreated in a new file, then the data is copied in chunks.
+pub fn copy(input: &File, file: &str, options: CopyOptions) -> Result<File> {
+    let input_format = input.format()?;
+    let output_format = options.format.unwrap_or(input_format);
+    let mut output = create_with(file, Options::new().format(output_format))?;
+    copy_definitions(&input.root, &mut output.root,
+                     input_format.is_netcdf4(), output_format.is_netcdf4(), &options)?;
+    data_mode(input.id)?;
+    data_mode(output.id)?;
+    copy_data(&input.root, &mut output.root, options.buffer_len)?;
+    Ok(output)
+}
+
+/// Recreate the dimensions, attributes, variables and sub-groups of `input`
+/// into `output`, in their original order.
+fn copy_definitions(input: &Group, output: &mut Group, input_netcdf4: bool,
+                    output_netcdf4: bool, options: &CopyOptions) -> Result<()> {
+    // dimensions of the parent groups are already defined
+    let mut dims: Vec<_> = input.dimensions.values().filter(|d| d.grp_id == input.id).collect();
+    dims.sort_by_key(|d| d.id);
+    for dim in dims {
+        if dim.is_unlimited() {
+            output.add_unlimited_dimension(&dim.name)?;
+        } else {
+            output.add_dimension(&dim.name, dim.len())?;
+        }
+    }
+    output.copy_attributes_from(input)?;
+
+    let mut vars: Vec<&Variable> = input.variables.values().collect();
+    vars.sort_by_key(|v| v.id);
+    for var in vars {
+        let dims: Vec<String> = var.dimensions.iter().map(|d| d.name.clone()).collect();
+        let var_options = if output_netcdf4 {
+            storage_options(var, input_netcdf4, options)?
+        } else {
+            VariableOptions::new()
         };
-    Ok(File {
-        id: ncid, 
-        name: file.to_string(),
-        root: root,
-    })
+        output.create_variable_with(&var.name, &dims, var.vartype, &var_options)?
+            .copy_attributes_from(var)?;
+    }
+
+    let mut groups: Vec<&Group> = input.sub_groups.values().collect();
+    groups.sort_by_key(|g| g.id);
+    for group in groups {
+        let sub_group = output.add_group(&group.name)?;
+        copy_definitions(group, sub_group, input_netcdf4, output_netcdf4, options)?;
+    }
+    Ok(())
+}
+
+/// Storage options of the copy of `var`: those of `var`, with the compression
+/// replaced if requested.
+fn storage_options(var: &Variable, input_netcdf4: bool, options: &CopyOptions) -> Result<VariableOptions> {
+    let mut chunking = None;
+    let mut compression = Compression { shuffle: false, deflate_level: None };
+    let mut fletcher32 = false;
+    let mut endianness = Endianness::Native;
+    if input_netcdf4 {
+        chunking = Some(var.chunking()?);
+        compression = var.compression()?;
+        fletcher32 = var.fletcher32()?;
+        endianness = var.endianness()?;
+    }
+    // scalar and string variables can't be compressed
+    if let Some(level) = options.deflate_level {
+        if !var.dimensions.is_empty() && var.vartype != NC_STRING {
+            compression = Compression {
+                shuffle: level > 0 && options.shuffle,
+                deflate_level: if level > 0 { Some(level) } else { None },
+            };
+        }
+    }
+    let mut var_options = VariableOptions::new()
+        .shuffle(compression.shuffle)
+        .fletcher32(fletcher32);
+    if let Some(level) = compression.deflate_level {
+        var_options = var_options.deflate(level);
+    }
+    let filtered = compression.deflate_level.is_some() || compression.shuffle || fletcher32;
+    match chunking {
+        // filters need chunked storage, chunk sizes are then left to libnetcdf
+        Some(Chunking::Contiguous) | Some(Chunking::Compact) if filtered => {}
+        Some(chunking) => var_options = var_options.chunking(chunking),
+        None => {}
+    }
+    if endianness != Endianness::Native {
+        var_options = var_options.endianness(endianness);
+    }
+    Ok(var_options)
+}
+
+/// Copy the data of the variables of `input` and its sub-groups into `output`.
+fn copy_data(input: &Group, output: &mut Group, buffer_len: usize) -> Result<()> {
+    for var in input.variables.values() {
+        let out_var = output.variables.get_mut(&var.name)
+            .ok_or_else(|| Error::VariableNotFound(var.name.clone()))?;
+        copy_values(var, out_var, buffer_len)?;
+    }
+    for group in input.sub_groups.values() {
+        let out_group = output.sub_groups.get_mut(&group.name)
+            .ok_or_else(|| Error::GroupNotFound(group.name.clone()))?;
+        copy_data(group, out_group, buffer_len)?;
+    }
+    Ok(())
+}
+
+/// Size in bytes of the values of a type, `None` for strings and user defined types.
+fn type_size(nctype: i32) -> Option<usize> {
+    match nctype {
+        NC_BYTE | NC_CHAR | NC_UBYTE => Some(1),
+        NC_SHORT | NC_USHORT => Some(2),
+        NC_INT | NC_UINT | NC_FLOAT => Some(4),
+        NC_INT64 | NC_UINT64 | NC_DOUBLE => Some(8),
+        _ => None,
+    }
+}
+
+/// Copy the values of `input` into `output`, reading blocks of `buffer_len` values
+/// (at least one slice along the first dimension).
+fn copy_values(input: &Variable, output: &mut Variable, buffer_len: usize) -> Result<()> {
+    let shape: Vec<usize> = input.dimensions.iter().map(|d| d.len() as usize).collect();
+    if shape.contains(&0) {
+        return Ok(());
+    }
+    if input.vartype == NC_STRING {
+        let values = input.get_strings()?;
+        return output.put_strings_at(&values, &vec![0; shape.len()], &shape);
+    }
+    let size = type_size(input.vartype).ok_or_else(|| Error::UnsupportedType {
+        name: input.name.clone(),
+        nctype: input.vartype,
+    })?;
+    let rows = shape.first().cloned().unwrap_or(1);
+    let row_len: usize = shape.iter().skip(1).product();
+    let block_rows = (buffer_len / row_len).clamp(1, rows);
+    // u64 elements keep the buffer aligned for any type
+    let mut buffer = vec![0u64; (block_rows * row_len * size).div_ceil(8)];
+    let mut start: Vec<size_t> = vec![0; shape.len()];
+    let mut count: Vec<size_t> = shape.iter().map(|l| *l as size_t).collect();
+    let mut row = 0;
+    while row < rows {
+        let block = block_rows.min(rows - row);
+        if !shape.is_empty() {
+            start[0] = row as size_t;
+            count[0] = block as size_t;
+        }
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            let err = nc_get_vara(input.grp_id, input.id, start.as_ptr(), count.as_ptr(),
+                                  buffer.as_mut_ptr() as *mut libc::c_void);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_get_vara", input.name.clone()));
+            }
+            let err = nc_put_vara(output.grp_id, output.id, start.as_ptr(), count.as_ptr(),
+                                  buffer.as_ptr() as *const libc::c_void);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_put_vara", output.name.clone()));
+            }
+        }
+        row += block;
+    }
+    Ok(())
 }
 
-impl File{
-    fn close(&mut self) {
+impl File {
+    fn new(ncid: i32, name: &str) -> File {
+        let root = Group {
+                name: "root".to_string(),
+                id: ncid,
+                variables: HashMap::new(),
+                attributes: HashMap::new(),
+                dimensions: HashMap::new(),
+                sub_groups: HashMap::new(),
+            };
+        File {
+            id: ncid,
+            name: name.to_string(),
+            root,
+            closed: false,
+        }
+    }
+
+    /// Get a group from its path, e.g. `file.group("science/level2")`;
+    /// `"/"` is the root group.
+    pub fn group(&self, path: &str) -> Option<&Group> {
+        self.root.group(path)
+    }
+
+    /// Mutable version of `File::group`.
+    pub fn group_mut(&mut self, path: &str) -> Option<&mut Group> {
+        self.root.group_mut(path)
+    }
+
+    /// On-disk format of the file.
+    pub fn format(&self) -> Result<Format> {
+        let mut format = 0i32;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_inq_format(self.id, &mut format);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_format", self.name.clone()));
+        }
+        Format::from_nc_format(format).ok_or_else(|| Error::UnsupportedType {
+            name: self.name.clone(),
+            nctype: format,
+        })
+    }
+
+    /// Underlying format (`NC_FORMAT_NC3`, `NC_FORMAT_NC_HDF5`, `NC_FORMAT_DAP2`, ...)
+    /// and mode flags the file was opened or created with.
+    pub fn format_extended(&self) -> Result<(i32, i32)> {
+        let mut format = 0i32;
+        let mut mode = 0i32;
+        let err: i32;
         unsafe {
             let _g = libnetcdf_lock.lock().unwrap();
-            let err = nc_close(self.id);
-            assert_eq!(err, NC_NOERR);
+            err = nc_inq_format_extended(self.id, &mut format, &mut mode);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_format_extended", self.name.clone()));
+        }
+        Ok((format, mode))
+    }
+
+    /// Close the file, returning any error reported by libnetcdf
+    /// (e.g. while flushing pending writes).
+    ///
+    /// Files are also closed when dropped, but errors are then ignored.
+    pub fn close(mut self) -> Result<()> {
+        self.close_id()
+    }
+
+    fn close_id(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
         }
+        self.closed = true;
+        let err: i32;
+        unsafe {
+            // a poisoned lock must not turn `drop` into a panic
+            let _g = libnetcdf_lock.lock().unwrap_or_else(|e| e.into_inner());
+            err = nc_close(self.id);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_close", self.name.clone()));
+        }
+        Ok(())
     }
 }
 
 impl Drop for File {
     fn drop(&mut self) {
         // Automatically close file when it goes out of scope
-        self.close();
+        let _ = self.close_id();
     }
 }
-
@@ -1,12 +1,14 @@
 use std::collections::HashMap;
 use std::ffi;
 use netcdf_sys::*;
-use dimension::{init_dimensions, Dimension};
-use attribute::{init_attributes, Attribute};
-use variable::{init_variable, init_variables, Variable, Numeric};
-use string_from_c_str;
-use NC_ERRORS;
+use dimension::{find_dimension, init_dimensions, Dimension};
+use attribute::{copy_attributes, delete_attribute, init_attributes, rename_attribute, Attribute};
+use variable::{init_variable, init_variables, Variable, VariableOptions, Numeric};
+use {c_string, data_mode, define_mode, is_netcdf4, string_from_c_str};
+use error::{Error, Result};
 use std::ptr;
+use libc;
+use ndarray::{ArrayBase, Data, Dimension as NdDimension};
 
 pub struct Group {
     pub name : String,
@@ -21,8 +23,9 @@ pub struct Group {
 // Write support for all variable types
 pub trait PutVar {
     fn get_nc_type(&self) -> i32;
-    fn put(&self, ncid: i32, varid: i32) -> Result<(), String> ;
+    fn put(&self, ncid: i32, varid: i32) -> Result<()> ;
     fn len(&self) -> usize;
+    fn is_empty(&self) -> bool { self.len() == 0 }
 }
 
 // This macro implements the trait PutVar for Vec<$type>
@@ -35,14 +38,14 @@ macro_rules! impl_putvar {
         impl PutVar for Vec<$type> {
             fn get_nc_type(&self) -> i32 { $nc_type }
             fn len(&self) -> usize { self.len() }
-            fn put(&self, ncid: i32, varid: i32) -> Result<(), String> {
+            fn put(&self, ncid: i32, varid: i32) -> Result<()> {
                 let err : i32;
                 unsafe {
                     let _g = libnetcdf_lock.lock().unwrap();
                     err = $nc_put_var(ncid, varid, self.as_ptr());
                 }
                 if err != NC_NOERR {
-                    return Err(NC_ERRORS.get(&err).unwrap().clone());
+                    return Err(Error::netcdf(err, stringify!($nc_put_var), format!("varid {}", varid)));
                 }
                 Ok(())
             }
@@ -59,38 +62,72 @@ impl_putvar!(u64, NC_UINT64, nc_put_var_ulonglong);
 impl_putvar!(f32, NC_FLOAT, nc_put_var_float);
 impl_putvar!(f64, NC_DOUBLE, nc_put_var_double);
 
+impl PutVar for Vec<String> {
+    fn get_nc_type(&self) -> i32 { NC_STRING }
+    fn len(&self) -> usize { self.len() }
+    fn put(&self, ncid: i32, varid: i32) -> Result<()> {
+        let values_c: Vec<ffi::CString> = self.iter()
+            .map(|v| c_string(v))
+            .collect::<Result<_>>()?;
+        let mut ptrs: Vec<*const libc::c_char> = values_c.iter().map(|v| v.as_ptr()).collect();
+        let err : i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_put_var_string(ncid, varid, ptrs.as_mut_ptr());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_put_var_string", format!("varid {}", varid)));
+        }
+        Ok(())
+    }
+}
+
 
 // Write support for all attribute types
 pub trait PutAttr {
     fn get_nc_type(&self) -> i32;
-    fn put(&self, ncid: i32, varid: i32, name: &str) -> Result<(), String> ;
+    fn put(&self, ncid: i32, varid: i32, name: &str) -> Result<()> ;
 }
 
-// This macro implements the trait PutAttr for $type
+// This macro implements the trait PutAttr for $type, Vec<$type> and &[$type]
 // It just avoid code repetition for all numeric types
 // (the only difference between each type beeing the 
 // netCDF funtion to call and the numeric identifier
 // of the type used by the libnetCDF library)
 macro_rules! impl_putattr {
     ($type: ty, $nc_type: ident, $nc_put_att: ident) => {
-        impl PutAttr for $type {
+        impl<'a> PutAttr for &'a [$type] {
             fn get_nc_type(&self) -> i32 { $nc_type }
-            fn put(&self, ncid: i32, varid: i32, name: &str) -> Result<(), String> {
-                let name_c: ffi::CString = ffi::CString::new(name.clone()).unwrap();
+            fn put(&self, ncid: i32, varid: i32, name: &str) -> Result<()> {
+                let name_c: ffi::CString = c_string(name)?;
                 let err : i32;
                 unsafe {
                     let _g = libnetcdf_lock.lock().unwrap();
-                    err = $nc_put_att(ncid, varid, name_c.as_ptr(), $nc_type, 1, self);
+                    err = $nc_put_att(ncid, varid, name_c.as_ptr(), $nc_type,
+                                      self.len() as u64, self.as_ptr());
                 }
                 if err != NC_NOERR {
-                    return Err(NC_ERRORS.get(&err).unwrap().clone());
+                    return Err(Error::netcdf(err, stringify!($nc_put_att), name));
                 }
                 Ok(())
             }
         }
+        impl PutAttr for Vec<$type> {
+            fn get_nc_type(&self) -> i32 { $nc_type }
+            fn put(&self, ncid: i32, varid: i32, name: &str) -> Result<()> {
+                self.as_slice().put(ncid, varid, name)
+            }
+        }
+        impl PutAttr for $type {
+            fn get_nc_type(&self) -> i32 { $nc_type }
+            fn put(&self, ncid: i32, varid: i32, name: &str) -> Result<()> {
+                (&[*self][..]).put(ncid, varid, name)
+            }
+        }
     }
 }
 impl_putattr!(i8, NC_BYTE, nc_put_att_schar);
+impl_putattr!(u8, NC_UBYTE, nc_put_att_ubyte);
 impl_putattr!(i16, NC_SHORT, nc_put_att_short);
 impl_putattr!(u16, NC_USHORT, nc_put_att_ushort);
 impl_putattr!(i32, NC_INT, nc_put_att_int);
@@ -102,9 +139,9 @@ impl_putattr!(f64, NC_DOUBLE, nc_put_att_double);
 
 impl PutAttr for String {
     fn get_nc_type(&self) -> i32 { NC_CHAR }
-    fn put(&self, ncid: i32, varid: i32, name: &str) -> Result<(), String> {
-        let name_c: ffi::CString = ffi::CString::new(name.clone()).unwrap();
-        let attr_c: ffi::CString = ffi::CString::new(self.clone()).unwrap();
+    fn put(&self, ncid: i32, varid: i32, name: &str) -> Result<()> {
+        let name_c: ffi::CString = c_string(name)?;
+        let attr_c: ffi::CString = c_string(self)?;
         let err : i32;
         unsafe {
             let _g = libnetcdf_lock.lock().unwrap();
@@ -113,7 +150,28 @@ impl PutAttr for String {
                 attr_c.to_bytes().len() as u64, attr_c.as_ptr());
         }
         if err != NC_NOERR {
-            return Err(NC_ERRORS.get(&err).unwrap().clone());
+            return Err(Error::netcdf(err, "nc_put_att_text", name));
+        }
+        Ok(())
+    }
+}
+
+impl PutAttr for Vec<String> {
+    fn get_nc_type(&self) -> i32 { NC_STRING }
+    fn put(&self, ncid: i32, varid: i32, name: &str) -> Result<()> {
+        let name_c: ffi::CString = c_string(name)?;
+        let values_c: Vec<ffi::CString> = self.iter()
+            .map(|v| c_string(v))
+            .collect::<Result<_>>()?;
+        let mut ptrs: Vec<*const libc::c_char> = values_c.iter().map(|v| v.as_ptr()).collect();
+        let err : i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_put_att_string(
+                ncid, varid, name_c.as_ptr(), ptrs.len() as u64, ptrs.as_mut_ptr());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_put_att_string", name));
         }
         Ok(())
     }
@@ -121,8 +179,9 @@ impl PutAttr for String {
 
 impl Group {
     pub fn add_attribute<T: PutAttr>(&mut self, name: &str, val: T) 
-            -> Result<(), String> {
-        try!(val.put(self.id, NC_GLOBAL, name));
+            -> Result<()> {
+        define_mode(self.id)?;
+        val.put(self.id, NC_GLOBAL, name)?;
         self.attributes.insert(
                 name.to_string().clone(),
                 Attribute {
@@ -136,9 +195,132 @@ impl Group {
         Ok(())
     }
 
+    /// Copy the attributes of `group` into this group (`nc_copy_att`),
+    /// e.g. the global attributes of another file.
+    pub fn copy_attributes_from(&mut self, group: &Group) -> Result<()> {
+        copy_attributes(&group.attributes, &mut self.attributes, self.id, NC_GLOBAL)
+    }
+
+    /// Rename a group attribute.
+    pub fn rename_attribute(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        rename_attribute(&mut self.attributes, self.id, NC_GLOBAL, old_name, new_name)
+    }
+
+    /// Delete a group attribute.
+    ///
+    /// libnetcdf can't delete variables, dimensions or groups: copy
+    /// the other ones into a new file instead, see `netcdf::copy`.
+    pub fn delete_attribute(&mut self, name: &str) -> Result<()> {
+        delete_attribute(&mut self.attributes, self.id, NC_GLOBAL, name)
+    }
+
+    /// Rename a variable of this group.
+    pub fn rename_variable(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        let varid = match self.variables.get(old_name) {
+            Some(var) => var.id,
+            None => return Err(Error::VariableNotFound(old_name.to_string())),
+        };
+        let name_c: ffi::CString = c_string(new_name)?;
+        define_mode(self.id)?;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_rename_var(self.id, varid, name_c.as_ptr());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_rename_var", old_name));
+        }
+        let mut var = self.variables.remove(old_name).unwrap();
+        var.name = new_name.to_string();
+        self.variables.insert(new_name.to_string(), var);
+        Ok(())
+    }
+
+    /// Rename a dimension defined in this group, also updating the variables
+    /// and sub-groups using it.
+    pub fn rename_dimension(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        let dimid = match self.dimensions.get(old_name) {
+            // dimensions of the parent groups are renamed from there
+            Some(dim) if dim.grp_id == self.id => dim.id,
+            _ => return Err(Error::DimensionNotFound(old_name.to_string())),
+        };
+        let name_c: ffi::CString = c_string(new_name)?;
+        define_mode(self.id)?;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_rename_dim(self.id, dimid, name_c.as_ptr());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_rename_dim", old_name));
+        }
+        let mut dim = self.dimensions[old_name].clone();
+        dim.name = new_name.to_string();
+        self.rename_visible_dimension(&dim, old_name, &[]);
+        Ok(())
+    }
+
+    /// Update the cached copies of the renamed dimension `dim` in this group
+    /// and its sub-groups, `inner_groups` being the ids of the groups between
+    /// the one defining `dim` (excluded) and this one.
+    fn rename_visible_dimension(&mut self, dim: &Dimension, old_name: &str, inner_groups: &[i32]) {
+        if self.dimensions.get(old_name).is_some_and(|d| d.id == dim.id) {
+            self.dimensions.remove(old_name);
+        }
+        // a dimension with the same name defined closer to this group shadows it
+        let shadowed = self.dimensions.get(&dim.name)
+            .is_some_and(|d| inner_groups.contains(&d.grp_id));
+        if !shadowed {
+            self.dimensions.insert(dim.name.clone(), dim.clone());
+        }
+        for var in self.variables.values_mut() {
+            for var_dim in var.dimensions.iter_mut().filter(|d| d.id == dim.id) {
+                var_dim.name = dim.name.clone();
+            }
+        }
+        for grp in self.sub_groups.values_mut() {
+            let mut inner_groups = inner_groups.to_vec();
+            inner_groups.push(grp.id);
+            grp.rename_visible_dimension(dim, old_name, &inner_groups);
+        }
+    }
+
+    /// Rename a sub-group of this group (NetCDF-4 files only).
+    pub fn rename_group(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        let grpid = match self.sub_groups.get(old_name) {
+            Some(grp) => grp.id,
+            None => return Err(Error::GroupNotFound(old_name.to_string())),
+        };
+        let name_c: ffi::CString = c_string(new_name)?;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_rename_grp(grpid, name_c.as_ptr());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_rename_grp", old_name));
+        }
+        let mut grp = self.sub_groups.remove(old_name).unwrap();
+        grp.name = new_name.to_string();
+        self.sub_groups.insert(new_name.to_string(), grp);
+        Ok(())
+    }
+
     pub fn add_dimension(&mut self, name: &str, len: u64) 
-            -> Result<(), String> {
-        let name_c: ffi::CString = ffi::CString::new(name.clone()).unwrap();
+            -> Result<()> {
+        self.def_dimension(name, len, false)
+    }
+
+    /// Add an unlimited (record) dimension, its length grows as
+    /// values are written along it.
+    pub fn add_unlimited_dimension(&mut self, name: &str) -> Result<()> {
+        self.def_dimension(name, NC_UNLIMITED as u64, true)
+    }
+
+    fn def_dimension(&mut self, name: &str, len: u64, unlimited: bool)
+            -> Result<()> {
+        let name_c: ffi::CString = c_string(name)?;
+        define_mode(self.id)?;
         let mut dimid: i32 = 0;
         let err : i32;
         unsafe {
@@ -146,57 +328,234 @@ impl Group {
             err = nc_def_dim(self.id, name_c.as_ptr(), len, &mut dimid);
         }
         if err != NC_NOERR {
-            return Err(NC_ERRORS.get(&err).unwrap().clone());
+            return Err(Error::netcdf(err, "nc_def_dim", name));
         }
-        self.dimensions.insert(
-                name.to_string().clone(),
-                Dimension {
-                    name: name.to_string().clone(),
-                    len: len,
-                    id: dimid
-                }
-            );
+        let dim = Dimension {
+                name: name.to_string(),
+                cached_len: len,
+                id: dimid,
+                grp_id: self.id,
+                unlimited,
+            };
+        self.add_visible_dimension(&dim);
         Ok(())
     }
 
-    // TODO this should probably take &Vec<&str> instead of &Vec<String>
-    pub fn add_variable<T: PutVar>(&mut self, name: &str, dims: &Vec<String>, data: &T) 
-                -> Result<(), String> {
+    /// Add the newly defined dimension `dim` to the cached dimensions of this
+    /// group and of its sub-groups, except those defining a dimension of the same name.
+    fn add_visible_dimension(&mut self, dim: &Dimension) {
+        self.dimensions.insert(dim.name.clone(), dim.clone());
+        for grp in self.sub_groups.values_mut() {
+            // a dimension with the same name defined by the sub-group shadows it
+            if grp.dimensions.get(&dim.name).is_some_and(|d| d.grp_id == grp.id) {
+                continue;
+            }
+            grp.add_visible_dimension(dim);
+        }
+    }
+
+    /// Look up a dimension by name in this group and its ancestors, from the file.
+    fn find_visible_dimension(&self, name: &str) -> Result<Dimension> {
+        let name_c: ffi::CString = c_string(name)?;
+        let mut dimid: i32 = 0;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_inq_dimid(self.id, name_c.as_ptr(), &mut dimid);
+        }
+        if err == NC_EBADDIM {
+            return Err(Error::DimensionNotFound(name.to_string()));
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_dimid", name));
+        }
+        find_dimension(self.id, dimid)
+    }
+
+    /// Create a sub-group (NetCDF-4 files only).
+    ///
+    /// Dimensions of this group are visible inside the new group, including
+    /// the ones added to this group afterwards.
+    pub fn add_group(&mut self, name: &str) -> Result<&mut Group> {
+        let name_c: ffi::CString = c_string(name)?;
+        let mut grpid: i32 = 0;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_def_grp(self.id, name_c.as_ptr(), &mut grpid);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_def_grp", name));
+        }
+        let grp = Group {
+                name: name.to_string(),
+                id: grpid,
+                variables: HashMap::new(),
+                attributes: HashMap::new(),
+                dimensions: self.dimensions.clone(),
+                sub_groups: HashMap::new(),
+            };
+        self.sub_groups.insert(name.to_string(), grp);
+        Ok(self.sub_groups.get_mut(name).unwrap())
+    }
+
+    /// Get a (nested) sub-group from a '/' separated path,
+    /// e.g. `grp.group("science/level2")`.
+    pub fn group(&self, path: &str) -> Option<&Group> {
+        let mut grp = self;
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            grp = grp.sub_groups.get(name)?;
+        }
+        Some(grp)
+    }
+
+    /// Mutable version of `Group::group`.
+    pub fn group_mut(&mut self, path: &str) -> Option<&mut Group> {
+        let mut grp = self;
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            grp = grp.sub_groups.get_mut(name)?;
+        }
+        Some(grp)
+    }
+
+    // TODO this should probably take &[&str] instead of &[String]
+    pub fn add_variable<T: PutVar>(&mut self, name: &str, dims: &[String], data: &T) 
+                -> Result<()> {
         let nctype: i32 = data.get_nc_type();
         let grp_id = self.id;
         let var = self.create_variable(name, dims, nctype)?;
+        data_mode(grp_id)?;
         data.put(grp_id, var.id)?; 
         Ok(())
     }
 
-    // TODO this should probably take &Vec<&str> instead of &Vec<String>
-    pub fn add_variable_with_fill_value<T: PutVar, N: Numeric>(&mut self, name: &str, dims: &Vec<String>, data: &T, fill_value: N) 
-                -> Result<(), String> {
+    // TODO this should probably take &[&str] instead of &[String]
+    pub fn add_variable_with_fill_value<T: PutVar, N: Numeric>(&mut self, name: &str, dims: &[String], data: &T, fill_value: N) 
+                -> Result<()> {
         let nctype: i32 = data.get_nc_type();
         let grp_id = self.id;
         let var = self.create_variable(name, dims, nctype)?;
         var.set_fill_value(fill_value)?;
+        data_mode(grp_id)?;
         data.put(grp_id, var.id)?; 
         Ok(())
     }
 
-    // TODO this should probably take &Vec<&str> instead of &Vec<String>
+    /// Create a Variable from a ndarray, `dims` naming each of its axes.
+    ///
+    /// Missing dimensions are created with the length of the matching axis,
+    /// existing ones must have the same length (unless they are unlimited).
+    pub fn add_variable_from_array<T, S, D>(&mut self, name: &str, dims: &[String], array: &ArrayBase<S, D>)
+                -> Result<()>
+        where T: Numeric, S: Data<Elem = T>, D: NdDimension
+    {
+        if dims.len() != array.ndim() {
+            return Err(Error::SliceLen { expected: dims.len(), found: array.ndim() });
+        }
+        for (dim_name, &len) in dims.iter().zip(array.shape()) {
+            let dim = match self.dimensions.get(dim_name) {
+                Some(dim) => Some(dim.clone()),
+                None => match self.find_visible_dimension(dim_name) {
+                    Ok(dim) => Some(dim),
+                    Err(Error::DimensionNotFound(_)) => None,
+                    Err(e) => return Err(e),
+                }
+            };
+            match dim {
+                Some(ref dim) if !dim.is_unlimited() && dim.len() != len as u64 => {
+                    return Err(Error::DimensionLen {
+                        name: dim_name.clone(),
+                        len: dim.len(),
+                        found: len
+                    });
+                }
+                Some(_) => {}
+                None => self.add_dimension(dim_name, len as u64)?,
+            }
+        }
+        let var = self.create_variable(name, dims, T::NCTYPE)?;
+        var.put_array_at(array, &vec![0; dims.len()])
+    }
+
+    /// Copy a variable, with its attributes and data, from another group or file
+    /// (`nc_copy_var`).
+    ///
+    /// Its dimensions are looked up by name, missing ones are created and
+    /// existing ones must be long enough (or unlimited).
+    pub fn copy_variable_from(&mut self, var: &Variable) -> Result<&mut Variable> {
+        // check all the dimensions before defining anything
+        let mut missing: Vec<&Dimension> = Vec::new();
+        for dim in &var.dimensions {
+            match self.find_visible_dimension(&dim.name) {
+                Ok(ref out_dim) if !out_dim.is_unlimited() && out_dim.len() < dim.len() => {
+                    return Err(Error::DimensionLen {
+                        name: dim.name.clone(),
+                        len: out_dim.len(),
+                        found: dim.len() as usize
+                    });
+                }
+                Ok(_) => {}
+                Err(Error::DimensionNotFound(_)) => missing.push(dim),
+                Err(e) => return Err(e),
+            }
+        }
+        for dim in missing {
+            if dim.is_unlimited() {
+                self.add_unlimited_dimension(&dim.name)?;
+            } else {
+                self.add_dimension(&dim.name, dim.len())?;
+            }
+        }
+        let name_c: ffi::CString = ffi::CString::new(var.name.clone()).unwrap();
+        let mut varid: i32 = 0;
+        // `nc_copy_var` defines the variable, then leaves define mode to copy the values
+        define_mode(self.id)?;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            let err = nc_copy_var(var.grp_id, var.id, self.id);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_copy_var", var.name.clone()));
+            }
+            let err = nc_inq_varid(self.id, name_c.as_ptr(), &mut varid);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_inq_varid", var.name.clone()));
+            }
+        }
+        init_variable(&mut self.variables, self.id, &self.dimensions, varid)?;
+        match self.variables.get_mut(&var.name) {
+            Some(var) => Ok(var),
+            None => Err(Error::VariableNotFound(var.name.clone()))
+        }
+    }
+
+    // TODO this should probably take &[&str] instead of &[String]
     /// Create a Variable into the dataset, without writting any data into it.
-    pub fn create_variable(&mut self, name: &str, dims: &Vec<String>, nctype: i32) 
-                -> Result<&mut Variable, String>
+    pub fn create_variable(&mut self, name: &str, dims: &[String], nctype: i32) 
+                -> Result<&mut Variable>
+    {
+        self.create_variable_with(name, dims, nctype, &VariableOptions::default())
+    }
+
+    /// Create a Variable into the dataset, with storage options
+    /// (chunking, ...), without writting any data into it.
+    pub fn create_variable_with(&mut self, name: &str, dims: &[String], nctype: i32,
+                                options: &VariableOptions) -> Result<&mut Variable>
     {
-        let name_c: ffi::CString = ffi::CString::new(name.clone()).unwrap();
+        let name_c: ffi::CString = c_string(name)?;
         let mut dimids: Vec<i32> = Vec::with_capacity(dims.len());
         let mut var_dims : Vec<Dimension> = Vec::with_capacity(dims.len());
         for dim_name in dims {
-            if !self.dimensions.contains_key(dim_name) {
-                return Err("Invalid dimension name".to_string());
+            match self.dimensions.get(dim_name) {
+                Some(dim) => var_dims.push(dim.clone()),
+                // may be a parent dimension defined after this group was loaded
+                None => var_dims.push(self.find_visible_dimension(dim_name)?),
             }
-            var_dims.push(self.dimensions.get(dim_name).unwrap().clone());
         }
         for dim in &var_dims {
             dimids.push(dim.id);
         }
+        options.check(is_netcdf4(self.id)?, &var_dims)?;
+        define_mode(self.id)?;
         let mut varid: i32 = 0;
         let err : i32;
         unsafe {
@@ -205,18 +564,24 @@ impl Group {
                                 dims.len() as i32, dimids.as_ptr(), &mut varid);
         }
         if err != NC_NOERR {
-            return Err(NC_ERRORS.get(&err).unwrap().clone());
-        }
-        init_variable(&mut self.variables, self.id, &mut self.dimensions, varid);
-        match self.variables.get_mut(name) {
-            Some(var) => Ok(var),
-            None => Err("Variable creation failed".into())
+            return Err(Error::netcdf(err, "nc_def_var", name));
         }
+        let mut vars = HashMap::new();
+        init_variable(&mut vars, self.id, &self.dimensions, varid)?;
+        let var = match vars.remove(name) {
+            Some(var) => var,
+            None => return Err(Error::VariableNotFound(name.to_string())),
+        };
+        // the variable stays defined even if libnetcdf rejects an option
+        let applied = options.apply(&var);
+        self.variables.insert(name.to_string(), var);
+        applied?;
+        Ok(self.variables.get_mut(name).unwrap())
     }
 }
 
 fn init_sub_groups(grp_id: i32, sub_groups: &mut HashMap<String, Group>,
-                   parent_dims: &HashMap<String, Dimension>) {
+                   parent_dims: &HashMap<String, Dimension>) -> Result<()> {
     let mut ngrps = 0i32;
     let mut grpids : Vec<i32>;
 
@@ -230,30 +595,40 @@ fn init_sub_groups(grp_id: i32, sub_groups: &mut HashMap<String, Group>,
         let _g = libnetcdf_lock.lock().unwrap();
         // Get the number of groups
         let mut err = nc_inq_grps(grp_id, &mut ngrps, ptr::null_mut());
-        assert_eq!(err, NC_NOERR);
-        // set the group capacity and len to the number of groups
-        grpids = Vec::with_capacity(ngrps as usize);
-        grpids.set_len(ngrps as usize);
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_grps", format!("ncid {}", grp_id)));
+        }
+        // set the group len to the number of groups
+        grpids = vec![0i32; ngrps as usize];
         // Get the list of group IDs
         err = nc_inq_grps(grp_id, &mut ngrps, grpids.as_mut_ptr());
-        assert_eq!(err, NC_NOERR);
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_grps", format!("ncid {}", grp_id)));
+        }
     }
     for i_grp in 0..ngrps {
         let mut namelen = 0u64;
         let c_str: &ffi::CStr;
+        let mut buf_vec: Vec<i8>;
         unsafe {
             let _g = libnetcdf_lock.lock().unwrap();
             // name length
             let err = nc_inq_grpname_len(grpids[i_grp as usize], &mut namelen);
-            assert_eq!(err, NC_NOERR);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_inq_grpname_len",
+                                         format!("ncid {}", grpids[i_grp as usize])));
+            }
             // name
-            let mut buf_vec = vec![0i8; (namelen+1) as usize];
+            buf_vec = vec![0i8; (namelen+1) as usize];
             let buf_ptr : *mut i8 = buf_vec.as_mut_ptr();
             let err = nc_inq_grpname(grpids[i_grp as usize], buf_ptr);
-            assert_eq!(err, NC_NOERR);
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_inq_grpname",
+                                         format!("ncid {}", grpids[i_grp as usize])));
+            }
             c_str = ffi::CStr::from_ptr(buf_ptr);
         }
-        let str_buf: String = string_from_c_str(c_str);
+        let str_buf: String = string_from_c_str(c_str)?;
 
         // Per NetCDF doc, "Dimensions are visible in their groups, and all 
         // child groups."
@@ -265,14 +640,15 @@ fn init_sub_groups(grp_id: i32, sub_groups: &mut HashMap<String, Group>,
                 dimensions: parent_dims.clone(),
                 sub_groups: HashMap::new(),
             };
-        init_group(&mut new_grp);
+        init_group(&mut new_grp)?;
         sub_groups.insert(str_buf.clone(), new_grp);
     }
+    Ok(())
 }
 
-pub fn init_group(grp: &mut Group) {
-    init_dimensions(&mut grp.dimensions, grp.id);
-    init_attributes(&mut grp.attributes, grp.id, NC_GLOBAL, -1);
-    init_variables(&mut grp.variables, grp.id, &grp.dimensions);
-    init_sub_groups(grp.id, &mut grp.sub_groups, &grp.dimensions);
+pub fn init_group(grp: &mut Group) -> Result<()> {
+    init_dimensions(&mut grp.dimensions, grp.id)?;
+    init_attributes(&mut grp.attributes, grp.id, NC_GLOBAL, -1)?;
+    init_variables(&mut grp.variables, grp.id, &grp.dimensions)?;
+    init_sub_groups(grp.id, &mut grp.sub_groups, &grp.dimensions)
 }
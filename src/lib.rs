@@ -65,12 +65,15 @@
 
 extern crate netcdf_sys;
 extern crate ndarray;
+extern crate chrono;
 
 #[macro_use]
 extern crate lazy_static;
 extern crate libc;
 
-use netcdf_sys::{libnetcdf_lock, nc_strerror};
+use netcdf_sys::{libnetcdf_lock, nc_enddef, nc_inq_format, nc_redef, nc_strerror,
+                 NC_EINDEFINE, NC_ENOTINDEFINE, NC_FORMAT_NETCDF4, NC_FORMAT_NETCDF4_CLASSIC,
+                 NC_NOERR};
 use std::ffi;
 use std::str;
 use std::path;
@@ -78,24 +81,107 @@ use std::env;
 use std::fs;
 use std::collections::HashMap;
 
+pub mod error;
 pub mod file;
 pub mod variable;
 pub mod attribute;
 pub mod group;
 pub mod dimension;
+pub mod decode;
+pub mod time;
+pub mod coordinate;
+pub mod cf;
 
+pub use error::{Error, Result};
 pub use file::open;
 pub use file::create;
+pub use file::create_with;
 pub use file::append;
+pub use file::copy;
 
-fn string_from_c_str(c_str: &ffi::CStr) -> String {
+fn string_from_c_str(c_str: &ffi::CStr) -> Result<String> {
     // see http://stackoverflow.com/questions/24145823/rust-ffi-c-string-handling
     // for good rundown
     let buf: &[u8] = c_str.to_bytes();
-    let str_slice: &str = str::from_utf8(buf).unwrap();
-    str_slice.to_owned()
+    match str::from_utf8(buf) {
+        Ok(str_slice) => Ok(str_slice.to_owned()),
+        Err(_) => Err(Error::InvalidUtf8(String::from_utf8_lossy(buf).into_owned())),
+    }
 }
 
+/// Convert a string passed to libnetcdf, which can't hold NUL bytes.
+fn c_string(s: &str) -> Result<ffi::CString> {
+    ffi::CString::new(s).map_err(|_| Error::InteriorNul(s.to_string()))
+}
+
+
+/// `nc_inq_format` of the file of `ncid`.
+fn inq_format(ncid: i32) -> Result<i32> {
+    let mut format = 0i32;
+    let err: i32;
+    unsafe {
+        let _g = libnetcdf_lock.lock().unwrap();
+        err = nc_inq_format(ncid, &mut format);
+    }
+    if err != NC_NOERR {
+        return Err(Error::netcdf(err, "nc_inq_format", format!("ncid {}", ncid)));
+    }
+    Ok(format)
+}
+
+/// Returns `true` if the file of `ncid` has to be switched between define and
+/// data mode by the caller: classic formats and netCDF-4 files restricted to the
+/// classic model. libnetcdf switches other netCDF-4 files by itself.
+fn has_define_mode(ncid: i32) -> Result<bool> {
+    Ok(inq_format(ncid)? != NC_FORMAT_NETCDF4)
+}
+
+/// Returns `true` if the file of `ncid` is a netCDF-4 (HDF5) file, even restricted
+/// to the classic model, supporting chunking, filters and endianness.
+fn is_netcdf4(ncid: i32) -> Result<bool> {
+    let format = inq_format(ncid)?;
+    Ok(format == NC_FORMAT_NETCDF4 || format == NC_FORMAT_NETCDF4_CLASSIC)
+}
+
+/// Put the file of `ncid` in define mode, needed to define or rename
+/// dimensions, variables and attributes.
+///
+/// The mode is not tracked, as some calls (e.g. `nc_copy_var`) switch it by
+/// themselves: `nc_redef` is always called, and the NC_EINDEFINE it returns
+/// for a file already in define mode is ignored.
+fn define_mode(ncid: i32) -> Result<()> {
+    if !has_define_mode(ncid)? {
+        return Ok(());
+    }
+    let err: i32;
+    unsafe {
+        let _g = libnetcdf_lock.lock().unwrap();
+        err = nc_redef(ncid);
+    }
+    if err != NC_NOERR && err != NC_EINDEFINE {
+        return Err(Error::netcdf(err, "nc_redef", format!("ncid {}", ncid)));
+    }
+    Ok(())
+}
+
+/// Leave define mode, needed to read or write the values of variables.
+///
+/// As for `define_mode`, `nc_enddef` is always called and the NC_ENOTINDEFINE
+/// it returns for a file already in data mode is ignored.
+fn data_mode(ncid: i32) -> Result<()> {
+    if !has_define_mode(ncid)? {
+        return Ok(());
+    }
+    let err: i32;
+    unsafe {
+        let _g = libnetcdf_lock.lock().unwrap();
+        err = nc_enddef(ncid);
+    }
+    if err != NC_NOERR && err != NC_ENOTINDEFINE {
+        return Err(Error::netcdf(err, "nc_enddef", format!("ncid {}", ncid)));
+    }
+    Ok(())
+}
 
 lazy_static! {
     pub static ref NC_ERRORS: HashMap<i32, String> = {
@@ -107,9 +193,9 @@ lazy_static! {
             unsafe {
                 let _g = libnetcdf_lock.lock().unwrap();
                 let msg : *const i8 = nc_strerror(i);
-                msg_cstr = &ffi::CStr::from_ptr(msg);
+                msg_cstr = ffi::CStr::from_ptr(msg);
             }
-            m.insert(i, string_from_c_str(msg_cstr));
+            m.insert(i, msg_cstr.to_string_lossy().into_owned());
         }
         m
     };
@@ -0,0 +1,526 @@
+//! Decoding of [CF time coordinates](http://cfconventions.org/Data/cf-conventions/cf-conventions-1.7/cf-conventions.html#time-coordinate):
+//! values counted in `units` such as `"hours since 1970-01-01 00:00:00"`,
+//! using the dates of the `calendar` attribute.
+//!
+//! ```no_run
+//! # let file = netcdf::open("forecast.nc").unwrap();
+//! let var = file.root.variables.get("time").unwrap();
+//! // missing values are None
+//! for time in var.times().unwrap().into_iter().flatten() {
+//!     println!("{}", time);
+//!     // only dates of the standard, proleptic_gregorian and julian calendars are actual days
+//!     let _naive: Option<chrono::NaiveDateTime> = time.to_naive_datetime();
+//! }
+//! ```
+
+use std::fmt;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use error::{Error, Result};
+use variable::Variable;
+
+const MICROSECONDS_PER_DAY: i64 = 86_400_000_000;
+
+/// Calendars of the CF conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Calendar {
+    /// Julian calendar before 1582-10-15, Gregorian calendar after (`standard` or `gregorian`)
+    Standard,
+    /// Gregorian calendar, extended before 1582-10-15
+    ProlepticGregorian,
+    /// Years of 365 days (`noleap` or `365_day`)
+    NoLeap,
+    /// Years of 366 days (`all_leap` or `366_day`)
+    AllLeap,
+    /// Months of 30 days
+    Day360,
+    /// Julian calendar, every fourth year being a leap year
+    Julian,
+}
+
+impl Calendar {
+    /// Parses the value of a `calendar` attribute, ignoring case.
+    pub fn from_name(name: &str) -> Result<Calendar> {
+        Ok(match name.trim().to_lowercase().as_str() {
+            "standard" | "gregorian" => Calendar::Standard,
+            "proleptic_gregorian" => Calendar::ProlepticGregorian,
+            "noleap" | "365_day" => Calendar::NoLeap,
+            "all_leap" | "366_day" => Calendar::AllLeap,
+            "360_day" => Calendar::Day360,
+            "julian" => Calendar::Julian,
+            _ => return Err(Error::TimeUnits(format!("unsupported calendar '{}'", name))),
+        })
+    }
+
+    /// CF name of the calendar
+    pub fn name(self) -> &'static str {
+        match self {
+            Calendar::Standard => "standard",
+            Calendar::ProlepticGregorian => "proleptic_gregorian",
+            Calendar::NoLeap => "noleap",
+            Calendar::AllLeap => "all_leap",
+            Calendar::Day360 => "360_day",
+            Calendar::Julian => "julian",
+        }
+    }
+
+    /// Dates of these calendars are actual days, sharing the same day numbers.
+    fn is_real(self) -> bool {
+        matches!(self, Calendar::Standard | Calendar::ProlepticGregorian | Calendar::Julian)
+    }
+
+    fn is_leap_year(self, year: i32) -> bool {
+        let julian = year.rem_euclid(4) == 0;
+        let gregorian = julian && (year % 100 != 0 || year % 400 == 0);
+        match self {
+            Calendar::Standard => if year > 1582 { gregorian } else { julian },
+            Calendar::ProlepticGregorian => gregorian,
+            Calendar::Julian => julian,
+            Calendar::NoLeap | Calendar::Day360 => false,
+            Calendar::AllLeap => true,
+        }
+    }
+
+    fn days_in_month(self, year: i32, month: u32) -> u32 {
+        match (self, month) {
+            (Calendar::Day360, _) => 30,
+            (_, 2) => if self.is_leap_year(year) { 29 } else { 28 },
+            (_, 4) | (_, 6) | (_, 9) | (_, 11) => 30,
+            _ => 31,
+        }
+    }
+
+    /// Number of the day `year-month-day`, counted from 1970-01-01 for real calendars.
+    fn day_number(self, year: i32, month: u32, day: u32) -> i64 {
+        match self {
+            Calendar::Standard if (year, month, day) >= (1582, 10, 15) =>
+                gregorian_day_number(year, month, day),
+            Calendar::Standard | Calendar::Julian => julian_day_number(year, month, day),
+            Calendar::ProlepticGregorian => gregorian_day_number(year, month, day),
+            Calendar::NoLeap | Calendar::AllLeap => {
+                let leap = self == Calendar::AllLeap;
+                let year_len = if leap { 366 } else { 365 };
+                year_len * i64::from(year) + days_before_month(leap, month) + i64::from(day) - 1
+            }
+            Calendar::Day360 => 360 * i64::from(year) + 30 * (i64::from(month) - 1) + i64::from(day) - 1,
+        }
+    }
+
+    /// Inverse of `day_number`
+    fn date(self, day_number: i64) -> (i32, u32, u32) {
+        match self {
+            Calendar::Standard if day_number >= gregorian_day_number(1582, 10, 15) =>
+                gregorian_date(day_number),
+            Calendar::Standard | Calendar::Julian => julian_date(day_number),
+            Calendar::ProlepticGregorian => gregorian_date(day_number),
+            Calendar::NoLeap | Calendar::AllLeap => {
+                let leap = self == Calendar::AllLeap;
+                let year_len = if leap { 366 } else { 365 };
+                let day_of_year = day_number.rem_euclid(year_len);
+                let month = (1..=12).rev().find(|m| days_before_month(leap, *m) <= day_of_year).unwrap();
+                let day = day_of_year - days_before_month(leap, month) + 1;
+                (day_number.div_euclid(year_len) as i32, month, day as u32)
+            }
+            Calendar::Day360 => {
+                let day_of_year = day_number.rem_euclid(360);
+                ((day_number.div_euclid(360)) as i32,
+                 (day_of_year / 30 + 1) as u32,
+                 (day_of_year % 30 + 1) as u32)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Calendar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+fn days_before_month(leap: bool, month: u32) -> i64 {
+    const DAYS: [i64; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    DAYS[month as usize - 1] + if leap && month > 2 { 1 } else { 0 }
+}
+
+// Day numbers of the Gregorian and Julian calendars, counting years from March 1st
+// so that leap days end them (see http://howardhinnant.github.io/date_algorithms.html)
+
+fn day_of_march_year(month: u32, day: u32) -> i64 {
+    let month_from_march = (i64::from(month) + 9) % 12;
+    (153 * month_from_march + 2) / 5 + i64::from(day) - 1
+}
+
+fn march_year_date(year: i64, day_of_year: i64) -> (i32, u32, u32) {
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+    let year = if month <= 2 { year + 1 } else { year };
+    (year as i32, month as u32, day as u32)
+}
+
+fn gregorian_day_number(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - if month <= 2 { 1 } else { 0 };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_march_year(month, day);
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn gregorian_date(day_number: i64) -> (i32, u32, u32) {
+    let days = day_number + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    march_year_date(era * 400 + year_of_era, day_of_year)
+}
+
+fn julian_day_number(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - if month <= 2 { 1 } else { 0 };
+    let era = year.div_euclid(4);
+    let year_of_era = year - era * 4;
+    let day_of_era = year_of_era * 365 + day_of_march_year(month, day);
+    // 1970-01-01 is 1969-12-19 in the Julian calendar
+    era * 1461 + day_of_era - 719_470
+}
+
+fn julian_date(day_number: i64) -> (i32, u32, u32) {
+    let days = day_number + 719_470;
+    let era = days.div_euclid(1461);
+    let day_of_era = days - era * 1461;
+    let year_of_era = (day_of_era - day_of_era / 1460) / 365;
+    let day_of_year = day_of_era - 365 * year_of_era;
+    march_year_date(era * 4 + year_of_era, day_of_year)
+}
+
+/// A date and time of a CF calendar, such as 2001-02-30 in the `360_day` calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Datetime {
+    pub calendar: Calendar,
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub microsecond: u32,
+}
+
+impl Datetime {
+    /// Returns an error if the date does not exist in `calendar`.
+    pub fn new(calendar: Calendar, year: i32, month: u32, day: u32,
+               hour: u32, minute: u32, second: u32) -> Result<Datetime> {
+        let time = Datetime { calendar, year, month, day, hour, minute, second, microsecond: 0 };
+        time.check()?;
+        Ok(time)
+    }
+
+    fn check(&self) -> Result<()> {
+        let valid = (1..=12).contains(&self.month)
+            && self.day >= 1
+            && self.day <= self.calendar.days_in_month(self.year, self.month)
+            // days skipped when switching to the Gregorian calendar
+            && !(self.calendar == Calendar::Standard
+                 && (self.year, self.month) == (1582, 10)
+                 && (5..15).contains(&self.day))
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60
+            && self.microsecond < 1_000_000;
+        if !valid {
+            return Err(Error::InvalidDatetime(format!("{} ({} calendar)", self, self.calendar)));
+        }
+        Ok(())
+    }
+
+    fn from_instant(calendar: Calendar, day_number: i64, microseconds: i64) -> Datetime {
+        let day_number = day_number + microseconds.div_euclid(MICROSECONDS_PER_DAY);
+        let microseconds = microseconds.rem_euclid(MICROSECONDS_PER_DAY);
+        let (year, month, day) = calendar.date(day_number);
+        let seconds = microseconds / 1_000_000;
+        Datetime {
+            calendar, year, month, day,
+            hour: (seconds / 3600) as u32,
+            minute: (seconds / 60 % 60) as u32,
+            second: (seconds % 60) as u32,
+            microsecond: (microseconds % 1_000_000) as u32,
+        }
+    }
+
+    /// Day number and microseconds since midnight
+    fn instant(&self) -> (i64, i64) {
+        let seconds = i64::from(self.hour) * 3600 + i64::from(self.minute) * 60 + i64::from(self.second);
+        (self.calendar.day_number(self.year, self.month, self.day),
+         seconds * 1_000_000 + i64::from(self.microsecond))
+    }
+
+    /// Converts the date to another calendar, which is only possible between the
+    /// `standard`, `proleptic_gregorian` and `julian` calendars, or to the same calendar.
+    pub fn to_calendar(&self, calendar: Calendar) -> Result<Datetime> {
+        if calendar == self.calendar {
+            return Ok(*self);
+        }
+        if !(calendar.is_real() && self.calendar.is_real()) {
+            return Err(Error::CalendarMismatch { expected: calendar, found: self.calendar });
+        }
+        self.check()?;
+        let (day_number, microseconds) = self.instant();
+        Ok(Datetime::from_instant(calendar, day_number, microseconds))
+    }
+
+    /// Builds a date of `calendar` from a (proleptic Gregorian) chrono date.
+    ///
+    /// The date is converted for the `standard` and `julian` calendars, and kept as is
+    /// for the other calendars, failing if it does not exist in them.
+    pub fn from_naive_datetime(datetime: &NaiveDateTime, calendar: Calendar) -> Result<Datetime> {
+        let time = Datetime {
+            calendar: Calendar::ProlepticGregorian,
+            year: datetime.year(),
+            month: datetime.month(),
+            day: datetime.day(),
+            hour: datetime.hour(),
+            minute: datetime.minute(),
+            second: datetime.second(),
+            // leap seconds are folded into the last microsecond
+            microsecond: (datetime.nanosecond() / 1000).min(999_999),
+        };
+        if calendar.is_real() {
+            return time.to_calendar(calendar);
+        }
+        let time = Datetime { calendar, ..time };
+        time.check()?;
+        Ok(time)
+    }
+
+    /// Converts the date to a (proleptic Gregorian) chrono date, `None` for calendars
+    /// without actual days or dates out of chrono range.
+    pub fn to_naive_datetime(&self) -> Option<NaiveDateTime> {
+        let time = self.to_calendar(Calendar::ProlepticGregorian).ok()?;
+        NaiveDate::from_ymd_opt(time.year, time.month, time.day)?
+            .and_hms_micro_opt(time.hour, time.minute, time.second, time.microsecond)
+    }
+}
+
+impl fmt::Display for Datetime {
+    /// Formats the date as `YYYY-MM-DD hh:mm:ss[.ffffff]`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+               self.year, self.month, self.day, self.hour, self.minute, self.second)?;
+        if self.microsecond != 0 {
+            write!(f, ".{:06}", self.microsecond)?;
+        }
+        Ok(())
+    }
+}
+
+/// Unit of CF time values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Microseconds,
+    Milliseconds,
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+}
+
+impl TimeUnit {
+    fn from_name(name: &str) -> Option<TimeUnit> {
+        Some(match name {
+            "microseconds" | "microsecond" | "us" => TimeUnit::Microseconds,
+            "milliseconds" | "millisecond" | "msec" | "ms" => TimeUnit::Milliseconds,
+            "seconds" | "second" | "secs" | "sec" | "s" => TimeUnit::Seconds,
+            "minutes" | "minute" | "mins" | "min" => TimeUnit::Minutes,
+            "hours" | "hour" | "hrs" | "hr" | "h" => TimeUnit::Hours,
+            "days" | "day" | "d" => TimeUnit::Days,
+            _ => return None,
+        })
+    }
+
+    /// CF name of the unit
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeUnit::Microseconds => "microseconds",
+            TimeUnit::Milliseconds => "milliseconds",
+            TimeUnit::Seconds => "seconds",
+            TimeUnit::Minutes => "minutes",
+            TimeUnit::Hours => "hours",
+            TimeUnit::Days => "days",
+        }
+    }
+
+    fn microseconds(self) -> i64 {
+        match self {
+            TimeUnit::Microseconds => 1,
+            TimeUnit::Milliseconds => 1000,
+            TimeUnit::Seconds => 1_000_000,
+            TimeUnit::Minutes => 60_000_000,
+            TimeUnit::Hours => 3_600_000_000,
+            TimeUnit::Days => MICROSECONDS_PER_DAY,
+        }
+    }
+}
+
+/// CF time units: values are counted in `unit` since `reference`,
+/// a UTC date of the variable calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeUnits {
+    pub unit: TimeUnit,
+    pub reference: Datetime,
+}
+
+impl TimeUnits {
+    /// Parses `units` such as `"days since 1970-1-1"` or
+    /// `"seconds since 1970-01-01T00:00:00+01:00"`.
+    pub fn parse(units: &str, calendar: Calendar) -> Result<TimeUnits> {
+        let invalid = || Error::TimeUnits(format!("'{}'", units));
+        let units_lower = units.trim().to_lowercase();
+        let mut parts = units_lower.splitn(2, " since ");
+        let unit = TimeUnit::from_name(parts.next().unwrap().trim()).ok_or_else(invalid)?;
+        let reference = parts.next().ok_or_else(invalid)?;
+        let (reference, offset) = parse_reference(reference.trim(), calendar).ok_or_else(invalid)?;
+        let (day_number, microseconds) = reference.instant();
+        Ok(TimeUnits {
+            unit,
+            // the reference is kept in UTC
+            reference: Datetime::from_instant(calendar, day_number, microseconds - offset),
+        })
+    }
+
+    /// Calendar of the dates
+    pub fn calendar(&self) -> Calendar {
+        self.reference.calendar
+    }
+
+    /// Date of a time value, rounded to the microsecond, `None` for NaN or huge values.
+    pub fn decode(&self, value: f64) -> Option<Datetime> {
+        let microseconds = (value * self.unit.microseconds() as f64).round();
+        // about 30 000 years
+        if !microseconds.is_finite() || microseconds.abs() >= 1e18 {
+            return None;
+        }
+        let (day_number, reference_microseconds) = self.reference.instant();
+        Some(Datetime::from_instant(self.calendar(), day_number,
+                                    reference_microseconds + microseconds as i64))
+    }
+
+    /// Time value of `time`, converted to the calendar of the units if needed.
+    pub fn encode(&self, time: &Datetime) -> Result<f64> {
+        let time = time.to_calendar(self.calendar())?;
+        time.check()?;
+        let (day_number, microseconds) = time.instant();
+        let (reference_day_number, reference_microseconds) = self.reference.instant();
+        let unit = self.unit.microseconds() as f64;
+        Ok((day_number - reference_day_number) as f64 * (MICROSECONDS_PER_DAY as f64 / unit)
+           + (microseconds - reference_microseconds) as f64 / unit)
+    }
+}
+
+impl fmt::Display for TimeUnits {
+    /// Formats the units as a CF `units` attribute
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} since {}", self.unit.name(), self.reference)
+    }
+}
+
+/// Parses a `[-]Y-M-D[( |T)h:m:s[.f]][Z| UTC|(+|-)hh[:mm]]` reference date,
+/// returning the local date and its time zone offset in microseconds.
+fn parse_reference(reference: &str, calendar: Calendar) -> Option<(Datetime, i64)> {
+    let (negative, reference) = match reference.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, reference),
+    };
+    let date_end = reference.find(&[' ', 't'][..]).unwrap_or(reference.len());
+    let mut date = reference[..date_end].split('-');
+    let year: i32 = date.next()?.parse().ok()?;
+    let month: u32 = date.next().map_or(Some(1), |m| m.parse().ok())?;
+    let day: u32 = date.next().map_or(Some(1), |d| d.parse().ok())?;
+    if date.next().is_some() {
+        return None;
+    }
+
+    let rest = &reference[date_end..];
+    let rest = rest.strip_prefix('t').unwrap_or(rest).trim();
+    let rest = rest.strip_suffix("utc").or_else(|| rest.strip_suffix('z')).unwrap_or(rest).trim();
+    let (time, zone) = match rest.find(&['+', '-'][..]) {
+        Some(i) => (rest[..i].trim(), Some(rest[i..].trim())),
+        None => (rest, None),
+    };
+
+    let (mut hour, mut minute, mut second, mut microsecond) = (0, 0, 0, 0);
+    if !time.is_empty() {
+        let mut fields = time.split(':');
+        hour = fields.next()?.parse().ok()?;
+        minute = fields.next().map_or(Some(0), |m| m.parse().ok())?;
+        let seconds: f64 = fields.next().map_or(Some(0.), |s| s.parse().ok())?;
+        if fields.next().is_some() || !(0. ..60.).contains(&seconds) {
+            return None;
+        }
+        second = seconds.trunc() as u32;
+        microsecond = ((seconds.fract() * 1e6).round() as u32).min(999_999);
+    }
+
+    let offset = match zone {
+        Some(zone) => {
+            let sign = if zone.starts_with('-') { -1 } else { 1 };
+            let digits = &zone[1..];
+            let (hours, minutes) = match digits.find(':') {
+                Some(i) => (&digits[..i], &digits[i + 1..]),
+                None if digits.len() > 2 => digits.split_at(digits.len() - 2),
+                None => (digits, "0"),
+            };
+            let hours: i64 = hours.parse().ok()?;
+            let minutes: i64 = minutes.parse().ok()?;
+            sign * (hours * 60 + minutes) * 60_000_000
+        }
+        None => 0,
+    };
+
+    let reference = Datetime {
+        calendar,
+        year: if negative { -year } else { year },
+        month, day, hour, minute, second, microsecond,
+    };
+    reference.check().ok()?;
+    Some((reference, offset))
+}
+
+impl Variable {
+    /// Parses the CF `units` and `calendar` attributes of a time variable,
+    /// the calendar defaulting to `standard`.
+    pub fn time_units(&self) -> Result<TimeUnits> {
+        let units = match self.attributes.get("units") {
+            Some(attr) => attr.text()?,
+            None => return Err(Error::TimeUnits(format!("variable '{}' has no units", self.name))),
+        };
+        let calendar = match self.attributes.get("calendar") {
+            Some(attr) => Calendar::from_name(&attr.text()?)?,
+            None => Calendar::Standard,
+        };
+        TimeUnits::parse(&units, calendar)
+    }
+
+    /// Fetchs the variable values as dates, see `time_units`.
+    ///
+    /// Values are unpacked following the CF conventions, missing values being `None`.
+    pub fn times(&self) -> Result<Vec<Option<Datetime>>> {
+        let units = self.time_units()?;
+        let values: Vec<f64> = self.values_decoded()?;
+        Ok(values.into_iter().map(|v| units.decode(v)).collect())
+    }
+
+    /// Fetchs a slice of the variable as dates, see `times`.
+    pub fn times_at(&self, indices: &[usize], slice_len: &[usize]) -> Result<Vec<Option<Datetime>>> {
+        let units = self.time_units()?;
+        let values: Vec<f64> = self.values_at_decoded(indices, slice_len)?;
+        Ok(values.into_iter().map(|v| units.decode(v)).collect())
+    }
+
+    /// Encodes `times` with the variable `units` and puts them at `indices`.
+    ///
+    /// Dates of another calendar are converted if possible, see `Datetime::to_calendar`.
+    pub fn put_times_at(&mut self, times: &[Datetime], indices: &[usize], slice_len: &[usize]) -> Result<()> {
+        let units = self.time_units()?;
+        let values = times.iter().map(|t| units.encode(t)).collect::<Result<Vec<f64>>>()?;
+        self.put_values_encoded(&values, indices, slice_len)
+    }
+}
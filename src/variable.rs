@@ -2,59 +2,203 @@ use std::marker::Sized;
 use std::ffi;
 use std::collections::HashMap;
 use netcdf_sys::*;
-use dimension::Dimension;
+use dimension::{find_dimension, Dimension};
 use group::PutAttr;
-use attribute::{init_attributes, Attribute};
-use string_from_c_str;
-use NC_ERRORS;
-use std::error::Error;
-use ndarray::{ArrayD};
+use attribute::{copy_attributes, delete_attribute, init_attributes, rename_attribute, Attribute};
+use {c_string, data_mode, define_mode, string_from_c_str};
+use error::{Error, Result};
+use ndarray::{ArrayBase, ArrayD, Axis, Data, DataMut, Dimension as NdDimension, ShapeBuilder, SliceOrIndex};
 use libc;
+use std::ptr;
 
 macro_rules! get_var_as_type {
     ( $me:ident, $nc_type:ident, $vec_type:ty, $nc_fn:ident , $cast:ident ) 
         => 
     {{
         if (!$cast) && ($me.vartype != $nc_type) {
-            return Err("Types are not equivalent and cast==false".to_string());
+            return Err(Error::TypeMismatch { expected: $nc_type, found: $me.vartype });
         }
-        let mut buf: Vec<$vec_type> = Vec::with_capacity($me.len as usize);
+        let mut buf: Vec<$vec_type> = vec![0 as $vec_type; $me.len() as usize];
         let err: i32;
+        data_mode($me.grp_id)?;
         unsafe {
             let _g = libnetcdf_lock.lock().unwrap();
-            buf.set_len($me.len as usize);
             err = $nc_fn($me.grp_id, $me.id, buf.as_mut_ptr());
         }
         if err != NC_NOERR {
-            return Err(NC_ERRORS.get(&err).unwrap().clone());
+            return Err(Error::netcdf(err, stringify!($nc_fn), $me.name.clone()));
         }
         Ok(buf)
     }};
 }
 
+/// Check a slice against the variable dimensions and return the number of
+/// values it holds, `stride` being `None` for contiguous slices.
+/// When `grow` is true, unlimited dimensions are not bounded (writes).
+fn check_slice(variable: &Variable, indices: &[usize], slice_len: &[usize],
+               stride: Option<&[usize]>, grow: bool) -> Result<usize> {
+    if indices.len() != variable.dimensions.len() {
+        return Err(Error::IndexLen {
+            expected: variable.dimensions.len(),
+            found: indices.len()
+        });
+    }
+    if indices.len() != slice_len.len() {
+        return Err(Error::SliceLen { expected: indices.len(), found: slice_len.len() });
+    }
+    if let Some(stride) = stride {
+        if indices.len() != stride.len() {
+            return Err(Error::StrideLen { expected: indices.len(), found: stride.len() });
+        }
+    }
+    let mut values_len: usize = 1;
+    for (i, dim) in variable.dimensions.iter().enumerate() {
+        let step = stride.map_or(1, |s| s[i]);
+        if slice_len[i] == 0 {
+            return Err(Error::ZeroSlice { dim: i });
+        }
+        if step == 0 {
+            return Err(Error::ZeroStride { dim: i });
+        }
+        values_len *= slice_len[i];
+        if grow && dim.is_unlimited() {
+            continue;
+        }
+        let len = dim.len();
+        if (indices[i] as u64) >= len {
+            return Err(Error::IndexOutOfRange { dim: i, index: indices[i], len });
+        }
+        if step == 1 && ((indices[i] + slice_len[i]) as u64) > len {
+            return Err(Error::SliceOutOfRange {
+                dim: i,
+                start: indices[i],
+                count: slice_len[i],
+                len
+            });
+        }
+        if ((indices[i] + (slice_len[i] - 1) * step) as u64) >= len {
+            return Err(Error::StridedSliceOutOfRange {
+                dim: i,
+                start: indices[i],
+                count: slice_len[i],
+                stride: step,
+                len
+            });
+        }
+    }
+    Ok(values_len)
+}
+
+/// Offset of the first value and length of a buffer holding a slice of
+/// `slice_len` values laid out following `imap` (negative `imap` values
+/// place the first value further in the buffer).
+fn mapped_extent(slice_len: &[usize], imap: &[isize]) -> Result<(usize, usize)> {
+    if imap.len() != slice_len.len() {
+        return Err(Error::ImapLen { expected: slice_len.len(), found: imap.len() });
+    }
+    let mut min_offset: isize = 0;
+    let mut max_offset: isize = 0;
+    for (count, step) in slice_len.iter().zip(imap) {
+        let reach = (*count as isize - 1) * step;
+        if reach < 0 {
+            min_offset += reach;
+        } else {
+            max_offset += reach;
+        }
+    }
+    Ok(((-min_offset) as usize, (max_offset - min_offset + 1) as usize))
+}
+
+/// `imap` of a column major (Fortran order) buffer holding a `slice_len` slice.
+fn fortran_imap(slice_len: &[usize]) -> Vec<isize> {
+    let mut imap = Vec::with_capacity(slice_len.len());
+    let mut step: isize = 1;
+    for count in slice_len {
+        imap.push(step);
+        step *= *count as isize;
+    }
+    imap
+}
+
+/// Numeric value of type `nctype` stored in native byte order in `bytes`,
+/// `None` for non numeric types.
+fn raw_value_as_f64(nctype: i32, bytes: &[u8; 8]) -> Option<f64> {
+    let b = bytes;
+    match nctype {
+        NC_BYTE => Some(f64::from(i8::from_ne_bytes([b[0]]))),
+        NC_CHAR | NC_UBYTE => Some(f64::from(b[0])),
+        NC_SHORT => Some(f64::from(i16::from_ne_bytes([b[0], b[1]]))),
+        NC_USHORT => Some(f64::from(u16::from_ne_bytes([b[0], b[1]]))),
+        NC_INT => Some(f64::from(i32::from_ne_bytes([b[0], b[1], b[2], b[3]]))),
+        NC_UINT => Some(f64::from(u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))),
+        NC_INT64 => Some(i64::from_ne_bytes(*b) as f64),
+        NC_UINT64 => Some(u64::from_ne_bytes(*b) as f64),
+        NC_FLOAT => Some(f64::from(f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))),
+        NC_DOUBLE => Some(f64::from_ne_bytes(*b)),
+        _ => None,
+    }
+}
+
 /// This trait allow an implicit cast when fetching 
 /// a netCDF variable
 pub trait Numeric {
+    /// The netCDF type matching `Self`
+    const NCTYPE: i32;
     /// Returns the whole variable as Vec<Self>
-    fn from_variable(variable: &Variable) -> Result<Vec<Self>, String>
+    fn from_variable(variable: &Variable) -> Result<Vec<Self>>
         where Self: Sized;
     /// Read the variable into a buffer and update its length.
-    fn read_variable_into_buffer(variable: &Variable, buffer: &mut Vec<Self>) -> Result<(), String>
+    fn read_variable_into_buffer(variable: &Variable, buffer: &mut Vec<Self>) -> Result<()>
         where Self: Sized;
     /// Read a slice of a variable into a buffer and update its length.
-    fn read_slice_into_buffer(variable: &Variable, indices: &[usize], slice_len: &[usize], buffer: &mut Vec<Self>) -> Result<(), String>
+    fn read_slice_into_buffer(variable: &Variable, indices: &[usize], slice_len: &[usize], buffer: &mut Vec<Self>) -> Result<()>
         where Self: Sized;
     /// Returns a slice of the variable as Vec<Self>
-    fn slice_from_variable(variable: &Variable, indices: &[usize], slice_len: &[usize]) -> Result<Vec<Self>, String>
+    fn slice_from_variable(variable: &Variable, indices: &[usize], slice_len: &[usize]) -> Result<Vec<Self>>
+        where Self: Sized;
+    /// Read a slice of a variable into a buffer of the exact slice size.
+    fn read_slice_into_slice(variable: &Variable, indices: &[usize], slice_len: &[usize], buffer: &mut [Self]) -> Result<()>
+        where Self: Sized;
+    /// Read a slice of a variable into memory laid out following `imap`,
+    /// the distance (in elements, possibly negative) between consecutive values along each dimension.
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must be valid for writes at every offset reached through `imap`.
+    unsafe fn read_mapped_slice(variable: &Variable, indices: &[usize], slice_len: &[usize], imap: &[isize], buffer: *mut Self) -> Result<()>
+        where Self: Sized;
+    /// Put a slice of values laid out in memory following `imap`, see `read_mapped_slice`.
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must be valid for reads at every offset reached through `imap`.
+    unsafe fn put_mapped_slice(variable: &mut Variable, indices: &[usize], slice_len: &[usize], imap: &[isize], buffer: *const Self) -> Result<()>
+        where Self: Sized;
+    /// Returns a slice of the variable laid out in a new buffer following `imap`,
+    /// the buffer is just big enough to hold every mapped value.
+    fn mapped_slice_from_variable(variable: &Variable, indices: &[usize], slice_len: &[usize], imap: &[isize]) -> Result<Vec<Self>>
+        where Self: Sized;
+    /// Returns a strided slice of the variable as Vec<Self>
+    fn strided_slice_from_variable(variable: &Variable, indices: &[usize], slice_len: &[usize], stride: &[usize]) -> Result<Vec<Self>>
         where Self: Sized;
     /// Returns a single indexed value of the variable as Self
-    fn single_value_from_variable(variable: &Variable, indices: &[usize]) -> Result<Self, String>
+    fn single_value_from_variable(variable: &Variable, indices: &[usize]) -> Result<Self>
         where Self: Sized;
     /// Put a single value into a netCDF variable
-    fn put_value_at(variable: &mut Variable, indices: &[usize], value: Self) -> Result<(), String>
+    fn put_value_at(variable: &mut Variable, indices: &[usize], value: Self) -> Result<()>
         where Self: Sized;
     /// put a SLICE of values into a netCDF variable at the given index
-    fn put_values_at(variable: &mut Variable, indices: &[usize], slice_len: &[usize], values: &[Self]) -> Result<(), String>
+    fn put_values_at(variable: &mut Variable, indices: &[usize], slice_len: &[usize], values: &[Self]) -> Result<()>
+        where Self: Sized;
+    /// put a strided SLICE of values into a netCDF variable at the given index
+    fn put_values_strided(variable: &mut Variable, indices: &[usize], slice_len: &[usize], stride: &[usize], values: &[Self]) -> Result<()>
+        where Self: Sized;
+    /// Returns the fill value of the variable (its `_FillValue`, or the libnetcdf
+    /// default for its type), `None` if filling is disabled.
+    fn fill_value_from_variable(variable: &Variable) -> Result<Option<Self>>
+        where Self: Sized;
+    /// Returns all the values of an attribute as Vec<Self>
+    fn from_attribute(attribute: &Attribute) -> Result<Vec<Self>>
         where Self: Sized;
     /// Returns `self` as a C (void *) pointer
     fn as_void_ptr(&self) -> *const libc::c_void;
@@ -73,232 +217,218 @@ macro_rules! impl_numeric {
         $nc_get_vara_type: ident,
         $nc_get_var1_type: ident, 
         $nc_put_var1_type: ident,
-        $nc_put_vara_type: ident) => {
+        $nc_put_vara_type: ident,
+        $nc_get_vars_type: ident,
+        $nc_put_vars_type: ident,
+        $nc_get_varm_type: ident,
+        $nc_put_varm_type: ident,
+        $nc_get_att_type: ident) => {
 
         impl Numeric for $sized_type {
+            const NCTYPE: i32 = $nc_type;
 
             // fetch ALL values from variable using `$nc_get_var`
-            fn from_variable(variable: &Variable) -> Result<Vec<$sized_type>, String> {
-                let mut buf: Vec<$sized_type> = Vec::with_capacity(variable.len as usize);
+            fn from_variable(variable: &Variable) -> Result<Vec<$sized_type>> {
+                let mut buf: Vec<$sized_type> = vec![0 as $sized_type; variable.len() as usize];
                 let err: i32;
+                data_mode(variable.grp_id)?;
                 unsafe {
                     let _g = libnetcdf_lock.lock().unwrap();
-                    buf.set_len(variable.len as usize);
                     err = $nc_get_var(variable.grp_id, variable.id, buf.as_mut_ptr());
                 }
                 if err != NC_NOERR {
-                    return Err(NC_ERRORS.get(&err).unwrap().clone());
+                    return Err(Error::netcdf(err, stringify!($nc_get_var), variable.name.clone()));
                 }
                 Ok(buf)
             }
             
             // Read all values from variable using `$nc_get_var` into a pre-allocated buffer
-            fn read_variable_into_buffer(variable: &Variable, buffer: &mut Vec<$sized_type>) -> Result<(), String> {
+            fn read_variable_into_buffer(variable: &Variable, buffer: &mut Vec<$sized_type>) -> Result<()> {
                 // check buffer capacity
-                if buffer.capacity() < variable.len as usize {
-                    return  Err(
-                        format!("Buffer is not big enough. (size {} needed)", variable.len)
-                    );
+                if buffer.capacity() < variable.len() as usize {
+                    return Err(Error::BufferTooSmall {
+                        needed: variable.len() as usize,
+                        capacity: buffer.capacity()
+                    });
                 }
+                // update the vector element count, without reallocating
+                buffer.clear();
+                buffer.resize(variable.len() as usize, 0 as $sized_type);
                 let err: i32;
+                data_mode(variable.grp_id)?;
                 unsafe {
                     let _g = libnetcdf_lock.lock().unwrap();
-                    // update the vector element count
-                    buffer.set_len(variable.len as usize);
                     // fill the buffer
                     err = $nc_get_var(variable.grp_id, variable.id, buffer.as_mut_ptr());
                 }
                 if err != NC_NOERR {
-                    return Err(NC_ERRORS.get(&err).unwrap().clone());
+                    return Err(Error::netcdf(err, stringify!($nc_get_var), variable.name.clone()));
                 }
                 Ok(())
             }
 
             // fetch ONE value from variable using `$nc_get_var1`
-            fn single_value_from_variable(variable: &Variable, indices: &[usize]) -> Result<$sized_type, String> {
-                // Check the length of `indices`
-                if indices.len() != variable.dimensions.len() {
-                    return Err("`indices` must has the same length as the variable dimensions".into());
-                }
-                for i in 0..indices.len() {
-                    if (indices[i] as u64) >= variable.dimensions[i].len {
-                        return Err("requested index is bigger than the dimension length".into());
-                    }
-                }
+            fn single_value_from_variable(variable: &Variable, indices: &[usize]) -> Result<$sized_type> {
+                let ones = vec![1; variable.dimensions.len()];
+                check_slice(variable, indices, &ones, None, false)?;
                 // initialize `buff` to 0
                 let mut buff: $sized_type = 0 as $sized_type;
                 let err: i32;
                 // Get a pointer to an array [size_t]
                 let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
                 let indices_ptr = indices.as_slice().as_ptr();
+                data_mode(variable.grp_id)?;
                 unsafe {
                     let _g = libnetcdf_lock.lock().unwrap();
                     //fn nc_get_var1(ncid: libc::c_int, varid: libc::c_int, indexp: *const size_t, ip: *mut libc::c_void)
                     err = $nc_get_var1_type(variable.grp_id, variable.id, indices_ptr, &mut buff);
                 }
                 if err != NC_NOERR {
-                    return Err(NC_ERRORS.get(&err).unwrap().clone());
+                    return Err(Error::netcdf(err, stringify!($nc_get_var1_type), variable.name.clone()));
                 }
                 Ok(buff)
             }
             
             // fetch a SLICE of values from variable using `$nc_get_vara`
-            fn slice_from_variable(variable: &Variable, indices: &[usize], slice_len: &[usize]) -> Result<Vec<$sized_type>, String> {
-                // Check the length of `indices`
-                if indices.len() != variable.dimensions.len() {
-                    return Err("`indices` must has the same length as the variable dimensions".into());
-                }
-                if indices.len() != slice_len.len() {
-                    return Err("`slice` must has the same length as the variable dimensions".into());
-                }
-                let mut values: Vec<$sized_type>;
-                let mut values_len: usize = 1;
-                for i in 0..indices.len() {
-                    if (indices[i] as u64) >= variable.dimensions[i].len {
-                        return Err("requested index is bigger than the dimension length".into());
-                    }
-                    if ((indices[i] + slice_len[i]) as u64) > variable.dimensions[i].len {
-                        return Err("requested slice is bigger than the dimension length".into());
-                    }
-                    // Compute the full size of the request values
-                    if slice_len[i] > 0 {
-                        values_len *= slice_len[i];
-                    } else {
-                        return Err("Each slice element must be superior than 0".into());
-                    }
-                }
+            fn slice_from_variable(variable: &Variable, indices: &[usize], slice_len: &[usize]) -> Result<Vec<$sized_type>> {
+                let values_len = check_slice(variable, indices, slice_len, None, false)?;
+                let mut values: Vec<$sized_type> = vec![0 as $sized_type; values_len];
+                Self::read_slice_into_slice(variable, indices, slice_len, &mut values)?;
+                Ok(values)
+            }
 
+            // read a SLICE of values from variable using `$nc_get_vara` into `buffer`
+            fn read_slice_into_slice(variable: &Variable, indices: &[usize], slice_len: &[usize], buffer: &mut [$sized_type]) -> Result<()> {
+                let values_len = check_slice(variable, indices, slice_len, None, false)?;
+                if buffer.len() != values_len {
+                    return Err(Error::ValuesLen { expected: values_len, found: buffer.len() });
+                }
                 let err: i32;
                 // Get a pointer to an array [size_t]
                 let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
                 let slice: Vec<size_t> = slice_len.iter().map(|i| *i as size_t).collect();
+                data_mode(variable.grp_id)?;
                 unsafe {
                     let _g = libnetcdf_lock.lock().unwrap();
-
-                    values = Vec::with_capacity(values_len);
-                    values.set_len(values_len);
-                    //let buff_ptr = values.as_mut_ptr() as *mut _ as *mut libc::c_void;
-                    //err = nc_get_vara(
                     err = $nc_get_vara_type(
                         variable.grp_id,
                         variable.id,
-                        indices.as_slice().as_ptr(),
-                        slice.as_slice().as_ptr(),
-                        values.as_mut_ptr()
+                        indices.as_ptr(),
+                        slice.as_ptr(),
+                        buffer.as_mut_ptr()
                     );
                 }
                 if err != NC_NOERR {
-                    return Err(NC_ERRORS.get(&err).unwrap().clone());
+                    return Err(Error::netcdf(err, stringify!($nc_get_vara_type), variable.name.clone()));
                 }
-                Ok(values)
+                Ok(())
             }
 
-            // read a SLICE of values from variable using `$nc_get_vara` into `buffer`
-            fn read_slice_into_buffer(variable: &Variable, indices: &[usize], slice_len: &[usize], buffer: &mut Vec<$sized_type>) -> Result<(), String> {
-                // Check the length of `indices`
-                if indices.len() != variable.dimensions.len() {
-                    return Err("`indices` must has the same length as the variable dimensions".into());
-                }
-                if indices.len() != slice_len.len() {
-                    return Err("`slice` must has the same length as the variable dimensions".into());
-                }
-                let mut values_len: usize = 1;
-                for i in 0..indices.len() {
-                    if (indices[i] as u64) >= variable.dimensions[i].len {
-                        return Err("requested index is bigger than the dimension length".into());
-                    }
-                    if ((indices[i] + slice_len[i]) as u64) > variable.dimensions[i].len {
-                        return Err("requested slice is bigger than the dimension length".into());
-                    }
-                    // Compute the full size of the request values
-                    if slice_len[i] > 0 {
-                        values_len *= slice_len[i];
-                    } else {
-                        return Err("Each slice element must be superior than 0".into());
-                    }
+            // read a SLICE of values from variable using `$nc_get_varm`,
+            // laid out in memory following `imap`
+            unsafe fn read_mapped_slice(variable: &Variable, indices: &[usize], slice_len: &[usize], imap: &[isize], buffer: *mut $sized_type) -> Result<()> {
+                check_slice(variable, indices, slice_len, None, false)?;
+                if imap.len() != slice_len.len() {
+                    return Err(Error::ImapLen { expected: slice_len.len(), found: imap.len() });
                 }
-                // check buffer capacity
-                if buffer.capacity() < values_len {
-                    return  Err(
-                        format!("Buffer is not big enough. (size {} needed)", values_len)
+                let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
+                let slice: Vec<size_t> = slice_len.iter().map(|i| *i as size_t).collect();
+                let stride: Vec<ptrdiff_t> = vec![1; slice_len.len()];
+                let imap: Vec<ptrdiff_t> = imap.iter().map(|i| *i as ptrdiff_t).collect();
+                let err: i32;
+                data_mode(variable.grp_id)?;
+                {
+                    let _g = libnetcdf_lock.lock().unwrap();
+                    err = $nc_get_varm_type(
+                        variable.grp_id,
+                        variable.id,
+                        indices.as_ptr(),
+                        slice.as_ptr(),
+                        stride.as_ptr(),
+                        imap.as_ptr(),
+                        buffer
                     );
                 }
+                if err != NC_NOERR {
+                    return Err(Error::netcdf(err, stringify!($nc_get_varm_type), variable.name.clone()));
+                }
+                Ok(())
+            }
 
+            // fetch a strided SLICE of values from variable using `$nc_get_vars`
+            fn strided_slice_from_variable(variable: &Variable, indices: &[usize], slice_len: &[usize], stride: &[usize]) -> Result<Vec<$sized_type>> {
+                let values_len = check_slice(variable, indices, slice_len, Some(stride), false)?;
+                let mut values: Vec<$sized_type> = vec![0 as $sized_type; values_len];
                 let err: i32;
-                // Get a pointer to an array [size_t]
                 let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
                 let slice: Vec<size_t> = slice_len.iter().map(|i| *i as size_t).collect();
+                let stride: Vec<ptrdiff_t> = stride.iter().map(|i| *i as ptrdiff_t).collect();
+                data_mode(variable.grp_id)?;
                 unsafe {
                     let _g = libnetcdf_lock.lock().unwrap();
-                    // update the vector element count
-                    buffer.set_len(values_len as usize);
-                    // read values into the buffer
-                    err = $nc_get_vara_type(
+                    err = $nc_get_vars_type(
                         variable.grp_id,
                         variable.id,
-                        indices.as_slice().as_ptr(),
-                        slice.as_slice().as_ptr(),
-                        buffer.as_mut_ptr()
+                        indices.as_ptr(),
+                        slice.as_ptr(),
+                        stride.as_ptr(),
+                        values.as_mut_ptr()
                     );
                 }
                 if err != NC_NOERR {
-                    return Err(NC_ERRORS.get(&err).unwrap().clone());
+                    return Err(Error::netcdf(err, stringify!($nc_get_vars_type), variable.name.clone()));
                 }
-                Ok(())
+                Ok(values)
             }
-            // put a SINGLE value into a netCDF variable at the given index
-            fn put_value_at(variable: &mut Variable, indices: &[usize], value: Self) -> Result<(), String> {
-                // Check the length of `indices`
-                if indices.len() != variable.dimensions.len() {
-                    return Err("`indices` must has the same length as the variable dimensions".into());
-                }
-                for i in 0..indices.len() {
-                    if (indices[i] as u64) >= variable.dimensions[i].len {
-                        return Err("requested index is bigger than the dimension length".into());
-                    }
+
+            // read a SLICE of values from variable using `$nc_get_vara` into `buffer`
+            fn read_slice_into_buffer(variable: &Variable, indices: &[usize], slice_len: &[usize], buffer: &mut Vec<$sized_type>) -> Result<()> {
+                let values_len = check_slice(variable, indices, slice_len, None, false)?;
+                // check buffer capacity
+                if buffer.capacity() < values_len {
+                    return Err(Error::BufferTooSmall {
+                        needed: values_len,
+                        capacity: buffer.capacity()
+                    });
                 }
+                // update the vector element count, without reallocating
+                buffer.clear();
+                buffer.resize(values_len, 0 as $sized_type);
+                Self::read_slice_into_slice(variable, indices, slice_len, buffer)
+            }
+            // put a SINGLE value into a netCDF variable at the given index
+            fn put_value_at(variable: &mut Variable, indices: &[usize], value: Self) -> Result<()> {
+                // unlimited dimensions grow to fit the written values
+                let ones = vec![1; variable.dimensions.len()];
+                check_slice(variable, indices, &ones, None, true)?;
                 let err: i32;
                 // Get a pointer to an array [size_t]
                 let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
                 let indices_ptr = indices.as_slice().as_ptr();
+                data_mode(variable.grp_id)?;
                 unsafe {
                     let _g = libnetcdf_lock.lock().unwrap();
                     err = $nc_put_var1_type(variable.grp_id, variable.id, indices_ptr, &value);
                 }
                 if err != NC_NOERR {
-                    return Err(NC_ERRORS.get(&err).unwrap().clone());
+                    return Err(Error::netcdf(err, stringify!($nc_put_var1_type), variable.name.clone()));
                 }
 
                 Ok(())
             }
             
             // put a SLICE of values into a netCDF variable at the given index
-            fn put_values_at(variable: &mut Variable, indices: &[usize], slice_len: &[usize], values: &[Self]) -> Result<(), String> {
-                if indices.len() != slice_len.len() {
-                    return Err("`slice` must has the same length as the variable dimensions".into());
-                }
-                let mut values_len = 0;
-                for i in 0..indices.len() {
-                    if (indices[i] as u64) >= variable.dimensions[i].len {
-                        return Err("requested index is bigger than the dimension length".into());
-                    }
-                    if ((indices[i] + slice_len[i]) as u64) > variable.dimensions[i].len {
-                        return Err("requested slice is bigger than the dimension length".into());
-                    }
-                    // Check for empty slice
-                    if slice_len[i] == 0 {
-                        return Err("Each slice element must be superior than 0".into());
-                    }
-                    values_len += slice_len[i];
-                }
-                if values_len  != values.len() {
-                    return Err("number of element in `values` doesn't match `slice_len`".into());
+            fn put_values_at(variable: &mut Variable, indices: &[usize], slice_len: &[usize], values: &[Self]) -> Result<()> {
+                let values_len = check_slice(variable, indices, slice_len, None, true)?;
+                if values_len != values.len() {
+                    return Err(Error::ValuesLen { expected: values_len, found: values.len() });
                 }
 
                 let err: i32;
                 // Get a pointer to an array [size_t]
                 let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
                 let slice: Vec<size_t> = slice_len.iter().map(|i| *i as size_t).collect();
+                data_mode(variable.grp_id)?;
                 unsafe {
                     let _g = libnetcdf_lock.lock().unwrap();
                     err = $nc_put_vara_type(
@@ -310,12 +440,136 @@ macro_rules! impl_numeric {
                     );
                 }
                 if err != NC_NOERR {
-                    return Err(NC_ERRORS.get(&err).unwrap().clone());
+                    return Err(Error::netcdf(err, stringify!($nc_put_vara_type), variable.name.clone()));
+                }
+
+                Ok(())
+            }
+
+            // put a strided SLICE of values into a netCDF variable using `$nc_put_vars`
+            fn put_values_strided(variable: &mut Variable, indices: &[usize], slice_len: &[usize], stride: &[usize], values: &[Self]) -> Result<()> {
+                let values_len = check_slice(variable, indices, slice_len, Some(stride), true)?;
+                if values_len != values.len() {
+                    return Err(Error::ValuesLen { expected: values_len, found: values.len() });
+                }
+                let err: i32;
+                let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
+                let slice: Vec<size_t> = slice_len.iter().map(|i| *i as size_t).collect();
+                let stride: Vec<ptrdiff_t> = stride.iter().map(|i| *i as ptrdiff_t).collect();
+                data_mode(variable.grp_id)?;
+                unsafe {
+                    let _g = libnetcdf_lock.lock().unwrap();
+                    err = $nc_put_vars_type(
+                        variable.grp_id,
+                        variable.id,
+                        indices.as_ptr(),
+                        slice.as_ptr(),
+                        stride.as_ptr(),
+                        values.as_ptr()
+                    );
+                }
+                if err != NC_NOERR {
+                    return Err(Error::netcdf(err, stringify!($nc_put_vars_type), variable.name.clone()));
+                }
+
+                Ok(())
+            }
+
+            // put a SLICE of values laid out in memory following `imap` using `$nc_put_varm`
+            unsafe fn put_mapped_slice(variable: &mut Variable, indices: &[usize], slice_len: &[usize], imap: &[isize], buffer: *const $sized_type) -> Result<()> {
+                check_slice(variable, indices, slice_len, None, true)?;
+                if imap.len() != slice_len.len() {
+                    return Err(Error::ImapLen { expected: slice_len.len(), found: imap.len() });
+                }
+                let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
+                let slice: Vec<size_t> = slice_len.iter().map(|i| *i as size_t).collect();
+                let stride: Vec<ptrdiff_t> = vec![1; slice_len.len()];
+                let imap: Vec<ptrdiff_t> = imap.iter().map(|i| *i as ptrdiff_t).collect();
+                let err: i32;
+                data_mode(variable.grp_id)?;
+                {
+                    let _g = libnetcdf_lock.lock().unwrap();
+                    err = $nc_put_varm_type(
+                        variable.grp_id,
+                        variable.id,
+                        indices.as_ptr(),
+                        slice.as_ptr(),
+                        stride.as_ptr(),
+                        imap.as_ptr(),
+                        buffer
+                    );
+                }
+                if err != NC_NOERR {
+                    return Err(Error::netcdf(err, stringify!($nc_put_varm_type), variable.name.clone()));
                 }
 
                 Ok(())
             }
 
+            // fetch a SLICE of values laid out following `imap` into a new buffer
+            fn mapped_slice_from_variable(variable: &Variable, indices: &[usize], slice_len: &[usize], imap: &[isize]) -> Result<Vec<$sized_type>> {
+                check_slice(variable, indices, slice_len, None, false)?;
+                let (first, buffer_len) = mapped_extent(slice_len, imap)?;
+                let mut values: Vec<$sized_type> = vec![0 as $sized_type; buffer_len];
+                unsafe {
+                    Self::read_mapped_slice(variable, indices, slice_len, imap,
+                                            values.as_mut_ptr().add(first))?;
+                }
+                Ok(values)
+            }
+
+            // fetch the fill value using `nc_inq_var_fill`
+            fn fill_value_from_variable(variable: &Variable) -> Result<Option<$sized_type>> {
+                let mut no_fill: i32 = 0;
+                // `nc_inq_var_fill` writes a value of the variable type,
+                // 8 bytes are enough for any numeric type
+                let mut bytes = [0u8; 8];
+                if raw_value_as_f64(variable.vartype, &bytes).is_none() {
+                    return Ok(None);
+                }
+                let err: i32;
+                unsafe {
+                    let _g = libnetcdf_lock.lock().unwrap();
+                    err = nc_inq_var_fill(variable.grp_id, variable.id, &mut no_fill,
+                                          bytes.as_mut_ptr() as *mut libc::c_void);
+                }
+                if err != NC_NOERR {
+                    return Err(Error::netcdf(err, "nc_inq_var_fill", variable.name.clone()));
+                }
+                if no_fill != 0 {
+                    return Ok(None);
+                }
+                if variable.vartype == $nc_type {
+                    let fill = unsafe { ptr::read_unaligned(bytes.as_ptr() as *const $sized_type) };
+                    return Ok(Some(fill));
+                }
+                // libnetcdf rounds values converted to a floating point type, other
+                // conversions are exact: an integer fill value must be representable
+                // in `$sized_type`, or no value read as `$sized_type` can match it
+                let is_float = $nc_type == NC_FLOAT || $nc_type == NC_DOUBLE;
+                Ok(raw_value_as_f64(variable.vartype, &bytes)
+                    .map(|v| (v, v as $sized_type))
+                    .filter(|&(v, fill)| is_float || fill as f64 == v)
+                    .map(|(_, fill)| fill))
+            }
+
+            // fetch ALL values from an attribute using `$nc_get_att`
+            fn from_attribute(attribute: &Attribute) -> Result<Vec<$sized_type>> {
+                let attlen = attribute.attlen()? as usize;
+                let mut buf: Vec<$sized_type> = vec![0 as $sized_type; attlen];
+                let name_c: ffi::CString = ffi::CString::new(attribute.name.clone()).unwrap();
+                let err: i32;
+                unsafe {
+                    let _g = libnetcdf_lock.lock().unwrap();
+                    err = $nc_get_att_type(attribute.file_id, attribute.var_id,
+                                           name_c.as_ptr(), buf.as_mut_ptr());
+                }
+                if err != NC_NOERR {
+                    return Err(Error::netcdf(err, stringify!($nc_get_att_type), attribute.name.clone()));
+                }
+                Ok(buf)
+            }
+
             fn as_void_ptr(&self) -> *const libc::c_void {
                 self as *const _ as *const libc::c_void
             }
@@ -323,12 +577,17 @@ macro_rules! impl_numeric {
     }
 }
 impl_numeric!(u8,
-	 NC_CHAR,
+	 NC_UBYTE,
 	 nc_get_var_uchar,
 	 nc_get_vara_uchar,
 	 nc_get_var1_uchar,
 	 nc_put_var1_uchar,
-	 nc_put_vara_uchar
+	 nc_put_vara_uchar,
+	 nc_get_vars_uchar,
+	 nc_put_vars_uchar,
+	 nc_get_varm_uchar,
+	 nc_put_varm_uchar,
+	 nc_get_att_uchar
 );
 
 impl_numeric!(i8,
@@ -337,7 +596,12 @@ impl_numeric!(i8,
 	 nc_get_vara_schar,
 	 nc_get_var1_schar,
 	 nc_put_var1_schar,
-	 nc_put_vara_schar
+	 nc_put_vara_schar,
+	 nc_get_vars_schar,
+	 nc_put_vars_schar,
+	 nc_get_varm_schar,
+	 nc_put_varm_schar,
+	 nc_get_att_schar
 );
 
 impl_numeric!(i16,
@@ -346,7 +610,12 @@ impl_numeric!(i16,
 	 nc_get_vara_short,
 	 nc_get_var1_short,
 	 nc_put_var1_short,
-	 nc_put_vara_short
+	 nc_put_vara_short,
+	 nc_get_vars_short,
+	 nc_put_vars_short,
+	 nc_get_varm_short,
+	 nc_put_varm_short,
+	 nc_get_att_short
 );
 
 impl_numeric!(u16,
@@ -355,7 +624,12 @@ impl_numeric!(u16,
 	 nc_get_vara_ushort,
 	 nc_get_var1_ushort,
 	 nc_put_var1_ushort,
-	 nc_put_vara_ushort
+	 nc_put_vara_ushort,
+	 nc_get_vars_ushort,
+	 nc_put_vars_ushort,
+	 nc_get_varm_ushort,
+	 nc_put_varm_ushort,
+	 nc_get_att_ushort
 );
 
 impl_numeric!(i32,
@@ -364,7 +638,12 @@ impl_numeric!(i32,
 	 nc_get_vara_int,
 	 nc_get_var1_int,
 	 nc_put_var1_int,
-	 nc_put_vara_int
+	 nc_put_vara_int,
+	 nc_get_vars_int,
+	 nc_put_vars_int,
+	 nc_get_varm_int,
+	 nc_put_varm_int,
+	 nc_get_att_int
 );
 
 impl_numeric!(u32,
@@ -373,7 +652,12 @@ impl_numeric!(u32,
 	 nc_get_vara_uint,
 	 nc_get_var1_uint,
 	 nc_put_var1_uint,
-	 nc_put_vara_uint
+	 nc_put_vara_uint,
+	 nc_get_vars_uint,
+	 nc_put_vars_uint,
+	 nc_get_varm_uint,
+	 nc_put_varm_uint,
+	 nc_get_att_uint
 );
 
 impl_numeric!(i64,
@@ -382,7 +666,12 @@ impl_numeric!(i64,
 	 nc_get_vara_longlong,
 	 nc_get_var1_longlong,
 	 nc_put_var1_longlong,
-	 nc_put_vara_longlong
+	 nc_put_vara_longlong,
+	 nc_get_vars_longlong,
+	 nc_put_vars_longlong,
+	 nc_get_varm_longlong,
+	 nc_put_varm_longlong,
+	 nc_get_att_longlong
 );
 
 impl_numeric!(u64,
@@ -391,7 +680,12 @@ impl_numeric!(u64,
 	 nc_get_vara_ulonglong,
 	 nc_get_var1_ulonglong,
 	 nc_put_var1_ulonglong,
-	 nc_put_vara_ulonglong
+	 nc_put_vara_ulonglong,
+	 nc_get_vars_ulonglong,
+	 nc_put_vars_ulonglong,
+	 nc_get_varm_ulonglong,
+	 nc_put_varm_ulonglong,
+	 nc_get_att_ulonglong
 );
 
 impl_numeric!(f32,
@@ -400,7 +694,12 @@ impl_numeric!(f32,
 	 nc_get_vara_float,
 	 nc_get_var1_float,
 	 nc_put_var1_float,
-	 nc_put_vara_float
+	 nc_put_vara_float,
+	 nc_get_vars_float,
+	 nc_put_vars_float,
+	 nc_get_varm_float,
+	 nc_put_varm_float,
+	 nc_get_att_float
 );
 
 impl_numeric!(f64,
@@ -409,11 +708,211 @@ impl_numeric!(f64,
 	 nc_get_vara_double,
 	 nc_get_var1_double,
 	 nc_put_var1_double,
-	 nc_put_vara_double
+	 nc_put_vara_double,
+	 nc_get_vars_double,
+	 nc_put_vars_double,
+	 nc_get_varm_double,
+	 nc_put_varm_double,
+	 nc_get_att_double
 );
 
 
+/// Storage layout of a variable (NetCDF-4 files only).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chunking {
+    /// Data stored in a single block
+    Contiguous,
+    /// Data stored in chunks, with one chunk size per dimension
+    Chunked(Vec<usize>),
+    /// Data stored in the object header (small variables only, libnetcdf >= 4.7.4)
+    Compact,
+}
+
+/// Compression settings of a variable (NetCDF-4 files only).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    /// `true` if the shuffle filter is applied before compression
+    pub shuffle: bool,
+    /// deflate (zlib) level from 1 to 9, `None` if the data is not deflated
+    pub deflate_level: Option<i32>,
+}
+
+/// Byte order of the data stored on disk (NetCDF-4 files only).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    /// Byte order of the machine writing the file
+    Native,
+    Little,
+    Big,
+}
+
+impl Endianness {
+    fn from_nc_endian(endian: i32) -> Endianness {
+        match endian {
+            NC_ENDIAN_LITTLE => Endianness::Little,
+            NC_ENDIAN_BIG => Endianness::Big,
+            _ => Endianness::Native,
+        }
+    }
+
+    fn nc_endian(self) -> i32 {
+        match self {
+            Endianness::Native => NC_ENDIAN_NATIVE,
+            Endianness::Little => NC_ENDIAN_LITTLE,
+            Endianness::Big => NC_ENDIAN_BIG,
+        }
+    }
+}
+
+/// Storage options applied when defining a variable,
+/// see `Group::create_variable_with`.
+///
+/// Options left unset use the libnetcdf defaults.
+#[derive(Debug, Clone, Default)]
+pub struct VariableOptions {
+    chunking: Option<Chunking>,
+    deflate_level: Option<i32>,
+    shuffle: bool,
+    fletcher32: bool,
+    endianness: Option<Endianness>,
+}
+
+impl VariableOptions {
+    pub fn new() -> VariableOptions {
+        VariableOptions::default()
+    }
+
+    /// Select contiguous, chunked or compact storage.
+    pub fn chunking(mut self, chunking: Chunking) -> VariableOptions {
+        self.chunking = Some(chunking);
+        self
+    }
+
+    /// Compress the data with deflate (zlib), `level` ranging from 1 (fastest) to 9 (smallest),
+    /// 0 disabling compression. Compressed variables are always chunked.
+    pub fn deflate(mut self, level: i32) -> VariableOptions {
+        self.deflate_level = if level == 0 { None } else { Some(level) };
+        self
+    }
+
+    /// Apply the shuffle filter, which usually improves the compression of numeric data.
+    pub fn shuffle(mut self, shuffle: bool) -> VariableOptions {
+        self.shuffle = shuffle;
+        self
+    }
+
+    /// Store a Fletcher32 checksum of each chunk, verified when reading.
+    pub fn fletcher32(mut self, fletcher32: bool) -> VariableOptions {
+        self.fletcher32 = fletcher32;
+        self
+    }
+
+    /// Force the byte order of the data stored on disk.
+    pub fn endianness(mut self, endianness: Endianness) -> VariableOptions {
+        self.endianness = Some(endianness);
+        self
+    }
+
+    /// Check the options against the file format and the dimensions of a variable,
+    /// before defining it: libnetcdf can't undefine a variable it then rejects.
+    pub(crate) fn check(&self, netcdf4: bool, dims: &[Dimension]) -> Result<()> {
+        let filters = [
+            ("deflate", self.deflate_level.is_some()),
+            ("shuffle", self.shuffle),
+            ("fletcher32", self.fletcher32),
+        ];
+        if !netcdf4 {
+            let options = [("chunking", self.chunking.is_some()), ("endianness", self.endianness.is_some())];
+            if let Some(&(option, _)) = options.iter().chain(filters.iter()).find(|o| o.1) {
+                return Err(Error::NotNetcdf4(option));
+            }
+        }
+        match self.chunking {
+            Some(Chunking::Chunked(ref sizes)) => {
+                if sizes.len() != dims.len() {
+                    return Err(Error::ChunkLen { expected: dims.len(), found: sizes.len() });
+                }
+                for (i, (&size, dim)) in sizes.iter().zip(dims).enumerate() {
+                    if size == 0 || (!dim.is_unlimited() && size as u64 > dim.len()) {
+                        return Err(Error::ChunkSize { dim: i, size, len: dim.len() });
+                    }
+                }
+            }
+            Some(Chunking::Contiguous) | Some(Chunking::Compact) => {
+                if let Some(dim) = dims.iter().find(|d| d.is_unlimited()) {
+                    return Err(Error::NotChunked(format!("the unlimited dimension '{}'", dim.name)));
+                }
+                if let Some(&(filter, _)) = filters.iter().find(|f| f.1) {
+                    return Err(Error::NotChunked(filter.to_string()));
+                }
+            }
+            None => {}
+        }
+        if let Some(level) = self.deflate_level {
+            if !(0..=9).contains(&level) {
+                return Err(Error::DeflateLevel(level));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the options, checked by `check`, to a freshly defined variable.
+    pub(crate) fn apply(&self, var: &Variable) -> Result<()> {
+        if let Some(ref chunking) = self.chunking {
+            let (storage, sizes): (i32, Vec<size_t>) = match *chunking {
+                Chunking::Contiguous => (NC_CONTIGUOUS, Vec::new()),
+                Chunking::Compact => (NC_COMPACT, Vec::new()),
+                Chunking::Chunked(ref sizes) => (NC_CHUNKED, sizes.iter().map(|s| *s as size_t).collect()),
+            };
+            let sizes_ptr = if sizes.is_empty() { ptr::null() } else { sizes.as_ptr() };
+            let err: i32;
+            unsafe {
+                let _g = libnetcdf_lock.lock().unwrap();
+                err = nc_def_var_chunking(var.grp_id, var.id, storage, sizes_ptr);
+            }
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_def_var_chunking", var.name.clone()));
+            }
+        }
+        if self.deflate_level.is_some() || self.shuffle {
+            let err: i32;
+            unsafe {
+                let _g = libnetcdf_lock.lock().unwrap();
+                err = nc_def_var_deflate(var.grp_id, var.id,
+                                         self.shuffle as i32,
+                                         self.deflate_level.is_some() as i32,
+                                         self.deflate_level.unwrap_or(0));
+            }
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_def_var_deflate", var.name.clone()));
+            }
+        }
+        if self.fletcher32 {
+            let err: i32;
+            unsafe {
+                let _g = libnetcdf_lock.lock().unwrap();
+                err = nc_def_var_fletcher32(var.grp_id, var.id, NC_FLETCHER32);
+            }
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_def_var_fletcher32", var.name.clone()));
+            }
+        }
+        if let Some(endianness) = self.endianness {
+            let err: i32;
+            unsafe {
+                let _g = libnetcdf_lock.lock().unwrap();
+                err = nc_def_var_endian(var.grp_id, var.id, endianness.nc_endian());
+            }
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_def_var_endian", var.name.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// This struct defines a netCDF variable.
+#[derive(Clone)]
 pub struct Variable {
     /// The variable name
     pub name : String,
@@ -422,46 +921,54 @@ pub struct Variable {
     /// the netcdf variable type identifier (from netcdf-sys)
     pub vartype : i32,
     pub id: i32,
-    /// total length; the product of all dim lengths
-    pub len: u64, 
     pub grp_id: i32,
 }
 
 impl Variable {
-    pub fn get_char(&self, cast: bool) -> Result<Vec<u8>, String> {
+    /// Total length, the product of all dimension lengths.
+    pub fn len(&self) -> u64 {
+        self.dimensions.iter().map(|d| d.len()).product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_char(&self, cast: bool) -> Result<Vec<u8>> {
         get_var_as_type!(self, NC_CHAR, u8, nc_get_var_uchar, cast)
     }
-    pub fn get_byte(&self, cast: bool) -> Result<Vec<i8>, String> {
+    pub fn get_byte(&self, cast: bool) -> Result<Vec<i8>> {
         get_var_as_type!(self, NC_BYTE, i8, nc_get_var_schar, cast)
     }
-    pub fn get_short(&self, cast: bool) -> Result<Vec<i16>, String> {
+    pub fn get_short(&self, cast: bool) -> Result<Vec<i16>> {
         get_var_as_type!(self, NC_SHORT, i16, nc_get_var_short, cast)
     }
-    pub fn get_ushort(&self, cast: bool) -> Result<Vec<u16>, String> {
+    pub fn get_ushort(&self, cast: bool) -> Result<Vec<u16>> {
         get_var_as_type!(self, NC_USHORT, u16, nc_get_var_ushort, cast)
     }
-    pub fn get_int(&self, cast: bool) -> Result<Vec<i32>, String> {
+    pub fn get_int(&self, cast: bool) -> Result<Vec<i32>> {
         get_var_as_type!(self, NC_INT, i32, nc_get_var_int, cast)
     }
-    pub fn get_uint(&self, cast: bool) -> Result<Vec<u32>, String> {
+    pub fn get_uint(&self, cast: bool) -> Result<Vec<u32>> {
         get_var_as_type!(self, NC_UINT, u32, nc_get_var_uint, cast)
     }
-    pub fn get_int64(&self, cast: bool) -> Result<Vec<i64>, String> {
+    pub fn get_int64(&self, cast: bool) -> Result<Vec<i64>> {
         get_var_as_type!(self, NC_INT64, i64, nc_get_var_longlong, cast)
     }
-    pub fn get_uint64(&self, cast: bool) -> Result<Vec<u64>, String> {
+    pub fn get_uint64(&self, cast: bool) -> Result<Vec<u64>> {
         get_var_as_type!(self, NC_UINT64, u64, nc_get_var_ulonglong, cast)
     }
-    pub fn get_float(&self, cast: bool) -> Result<Vec<f32>, String> {
+    pub fn get_float(&self, cast: bool) -> Result<Vec<f32>> {
         get_var_as_type!(self, NC_FLOAT, f32, nc_get_var_float, cast)
     }
-    pub fn get_double(&self, cast: bool) -> Result<Vec<f64>, String> {
+    pub fn get_double(&self, cast: bool) -> Result<Vec<f64>> {
         get_var_as_type!(self, NC_DOUBLE, f64, nc_get_var_double, cast)
     }
 
     pub fn add_attribute<T: PutAttr>(&mut self, name: &str, val: T) 
-            -> Result<(), String> {
-        try!(val.put(self.grp_id, self.id, name));
+            -> Result<()> {
+        define_mode(self.grp_id)?;
+        val.put(self.grp_id, self.id, name)?;
         self.attributes.insert(
                 name.to_string().clone(),
                 Attribute {
@@ -475,26 +982,42 @@ impl Variable {
         Ok(())
     }
 
+    /// Rename an attribute of the variable.
+    pub fn rename_attribute(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        rename_attribute(&mut self.attributes, self.grp_id, self.id, old_name, new_name)
+    }
+
+    /// Delete an attribute of the variable.
+    pub fn delete_attribute(&mut self, name: &str) -> Result<()> {
+        delete_attribute(&mut self.attributes, self.grp_id, self.id, name)
+    }
+
+    /// Copy the attributes of `var` into this variable (`nc_copy_att`),
+    /// e.g. from a variable of another file.
+    pub fn copy_attributes_from(&mut self, var: &Variable) -> Result<()> {
+        copy_attributes(&var.attributes, &mut self.attributes, self.grp_id, self.id)
+    }
+
     /// Fetchs variable values, and cast them if needed.
     ///
     /// ```
     /// // let values: Vec<f64> = some_variable.values().unwrap();
     /// ```
     ///
-    pub fn values<T: Numeric>(&self) -> Result<Vec<T>, String> {
+    pub fn values<T: Numeric>(&self) -> Result<Vec<T>> {
         T::from_variable(self)
     }
     
     /// Read a slice of a variable into a buffer,
     /// the buffer must have a capacity at least equal as the number of elements of the slice.
     /// The buffer length (not its capacity) will be updated.
-    pub fn read_values_into_buffer<T: Numeric>(&self, buffer: &mut Vec<T>) -> Result<(), String> {
+    pub fn read_values_into_buffer<T: Numeric>(&self, buffer: &mut Vec<T>) -> Result<()> {
         T::read_variable_into_buffer(self, buffer)
     }
 
     ///  Fetchs one specific value at specific indices
     ///  indices must has the same length as self.dimensions.
-    pub fn value_at<T: Numeric>(&self, indices: &[usize]) -> Result<T, String> {
+    pub fn value_at<T: Numeric>(&self, indices: &[usize]) -> Result<T> {
         T::single_value_from_variable(self, indices)
     }
 
@@ -505,14 +1028,38 @@ impl Variable {
     /// * all 'slice' elements must be > 0.
     ///
     /// The buffer length (not its capacity) will be updated.
-    pub fn read_slice_into_buffer<T: Numeric>(&self, indices: &[usize], slice_len: &[usize], buffer: &mut Vec<T>) -> Result<(), String> {
+    pub fn read_slice_into_buffer<T: Numeric>(&self, indices: &[usize], slice_len: &[usize], buffer: &mut Vec<T>) -> Result<()> {
         T::read_slice_into_buffer(self, indices, slice_len, buffer)
     }
 
+    /// Read a slice of a variable starting at `indices` straight into `array`
+    /// (e.g. a preallocated tile or a view of it), the slice shape being the array shape.
+    ///
+    /// Arrays which are not contiguous in standard (row major) layout are
+    /// filled in place through `nc_get_varm`.
+    pub fn read_slice_into_array<T, S, D>(&self, indices: &[usize], array: &mut ArrayBase<S, D>) -> Result<()>
+        where T: Numeric, S: DataMut<Elem = T>, D: NdDimension
+    {
+        if array.ndim() != self.dimensions.len() {
+            return Err(Error::SliceLen { expected: self.dimensions.len(), found: array.ndim() });
+        }
+        if array.is_empty() {
+            return Ok(());
+        }
+        let slice_len = array.shape().to_vec();
+        if let Some(buffer) = array.as_slice_mut() {
+            return T::read_slice_into_slice(self, indices, &slice_len, buffer);
+        }
+        let imap = array.strides().to_vec();
+        // `as_mut_ptr` points at the first element of the array, `imap` (the array
+        // strides) only reaches elements of the array
+        unsafe { T::read_mapped_slice(self, indices, &slice_len, &imap, array.as_mut_ptr()) }
+    }
+
     /// Fetchs a slice of values
     /// indices must has the same length as self.dimensions.
     /// All slice elements must be > 0.
-    pub fn values_at<T: Numeric>(&self, indices: &[usize], slice_len: &[usize]) -> Result<Vec<T>, String> {
+    pub fn values_at<T: Numeric>(&self, indices: &[usize], slice_len: &[usize]) -> Result<Vec<T>> {
         T::slice_from_variable(self, indices, slice_len)
     }
 
@@ -523,47 +1070,399 @@ impl Variable {
     /// // let values: ArrayD<f64> = some_variable.as_array().unwrap();
     /// ```
     ///
-    pub fn as_array<T: Numeric>(&self) -> Result<ArrayD<T>, Box<Error>> {
+    pub fn as_array<T: Numeric>(&self) -> Result<ArrayD<T>> {
         let mut dims: Vec<usize> = Vec::new();
         for dim in &self.dimensions {
-            dims.push(dim.len as usize);
+            dims.push(dim.len() as usize);
         }
         let values = self.values()?;
         Ok(ArrayD::<T>::from_shape_vec(dims, values)?)
     }
     
     /// Fetchs variable slice as a ndarray.
-    pub fn array_at<T: Numeric>(&self, indices: &[usize], slice_len: &[usize]) -> Result<ArrayD<T>, Box<Error>> {
+    pub fn array_at<T: Numeric>(&self, indices: &[usize], slice_len: &[usize]) -> Result<ArrayD<T>> {
         let values = self.values_at(indices, slice_len)?;
         Ok(ArrayD::<T>::from_shape_vec(slice_len, values)?)
     }
 
+    /// Fetchs a strided slice of values: `slice_len[i]` values taken every
+    /// `stride[i]` elements from `indices[i]` along each dimension.
+    pub fn values_strided<T: Numeric>(&self, indices: &[usize], slice_len: &[usize], stride: &[usize]) -> Result<Vec<T>> {
+        T::strided_slice_from_variable(self, indices, slice_len, stride)
+    }
+
+    /// Fetchs a strided slice of values as a ndarray, see `values_strided`.
+    pub fn array_strided<T: Numeric>(&self, indices: &[usize], slice_len: &[usize], stride: &[usize]) -> Result<ArrayD<T>> {
+        let values = self.values_strided(indices, slice_len, stride)?;
+        Ok(ArrayD::<T>::from_shape_vec(slice_len, values)?)
+    }
+
+    /// Fetchs a slice of values described by `ndarray::s![]`, e.g. `s![.., 10..20;2, -1]`.
+    ///
+    /// * negative indices count from the end of the dimension,
+    /// * negative steps read the dimension in reverse order (as ndarray does),
+    /// * single indices drop their dimension from the returned array,
+    /// * missing trailing dimensions are read entirely.
+    pub fn array_slice<T: Numeric, S: AsRef<[SliceOrIndex]>>(&self, info: S) -> Result<ArrayD<T>> {
+        let info = info.as_ref();
+        if info.len() > self.dimensions.len() {
+            return Err(Error::IndexLen { expected: self.dimensions.len(), found: info.len() });
+        }
+        let mut indices: Vec<usize> = Vec::with_capacity(self.dimensions.len());
+        let mut slice_len: Vec<usize> = Vec::with_capacity(self.dimensions.len());
+        let mut stride: Vec<usize> = Vec::with_capacity(self.dimensions.len());
+        // shape of the returned array, and its axes read in reverse order
+        let mut shape: Vec<usize> = Vec::new();
+        let mut inverted: Vec<usize> = Vec::new();
+        for (i, dim) in self.dimensions.iter().enumerate() {
+            let len = dim.len() as isize;
+            let abs_index = |index: isize, upper: isize| {
+                let abs = if index < 0 { len + index } else { index };
+                if abs < 0 || abs > upper {
+                    return Err(Error::SliceIndexOutOfRange { dim: i, index, len: dim.len() });
+                }
+                Ok(abs)
+            };
+            let elt = info.get(i).cloned()
+                .unwrap_or(SliceOrIndex::Slice { start: 0, end: None, step: 1 });
+            match elt {
+                SliceOrIndex::Index(index) => {
+                    indices.push(abs_index(index, len - 1)? as usize);
+                    slice_len.push(1);
+                    stride.push(1);
+                }
+                SliceOrIndex::Slice { start, end, step } => {
+                    if step == 0 {
+                        return Err(Error::ZeroStride { dim: i });
+                    }
+                    let start = abs_index(start, len)?;
+                    let end = abs_index(end.unwrap_or(len), len)?.max(start);
+                    let abs_step = step.abs();
+                    let count = (end - start + abs_step - 1) / abs_step;
+                    // with a negative step the slice starts from `end - 1`
+                    let first = if step > 0 || count == 0 { start } else { end - 1 - (count - 1) * abs_step };
+                    if step < 0 {
+                        inverted.push(shape.len());
+                    }
+                    shape.push(count as usize);
+                    indices.push(first as usize);
+                    slice_len.push(count as usize);
+                    stride.push(abs_step as usize);
+                }
+            }
+        }
+        if slice_len.contains(&0) {
+            return Ok(ArrayD::<T>::from_shape_vec(shape, Vec::new())?);
+        }
+        let values = if stride.iter().all(|s| *s == 1) {
+            self.values_at(&indices, &slice_len)?
+        } else {
+            self.values_strided(&indices, &slice_len, &stride)?
+        };
+        let mut array = ArrayD::<T>::from_shape_vec(shape, values)?;
+        for axis in inverted {
+            array.invert_axis(Axis(axis));
+        }
+        Ok(array)
+    }
+
+    /// Put a ndarray at `start`, the array must have the same number of dimensions
+    /// as the variable. Any memory layout is accepted (e.g. transposed views or
+    /// Fortran ordered arrays), non standard layouts are written through `nc_put_varm`.
+    pub fn put_array_at<T, S, D>(&mut self, array: &ArrayBase<S, D>, start: &[usize]) -> Result<()>
+        where T: Numeric, S: Data<Elem = T>, D: NdDimension
+    {
+        if array.ndim() != self.dimensions.len() {
+            return Err(Error::SliceLen { expected: self.dimensions.len(), found: array.ndim() });
+        }
+        if array.is_empty() {
+            // nothing to write, `start` must still be valid
+            let ones = vec![1; self.dimensions.len()];
+            return check_slice(self, start, &ones, None, true).map(|_| ());
+        }
+        if let Some(values) = array.as_slice() {
+            return T::put_values_at(self, start, array.shape(), values);
+        }
+        let imap = array.strides().to_vec();
+        // `as_ptr` points at the first element of the array, `imap` (the array
+        // strides) only reaches elements of the array
+        unsafe { T::put_mapped_slice(self, start, array.shape(), &imap, array.as_ptr()) }
+    }
+
+    /// Fetchs a slice of values laid out following `imap`: the distance (in elements)
+    /// in the returned buffer between consecutive values along each dimension.
+    ///
+    /// e.g. `imap = [1, slice_len[0]]` returns a 2D slice in column major order.
+    pub fn values_mapped<T: Numeric>(&self, indices: &[usize], slice_len: &[usize], imap: &[isize]) -> Result<Vec<T>> {
+        T::mapped_slice_from_variable(self, indices, slice_len, imap)
+    }
+
+    /// Put a slice of values laid out in `values` following `imap`, see `values_mapped`.
+    pub fn put_values_mapped<T: Numeric>(&mut self, values: &[T], indices: &[usize], slice_len: &[usize], imap: &[isize]) -> Result<()> {
+        let (first, needed) = mapped_extent(slice_len, imap)?;
+        if values.len() < needed {
+            return Err(Error::BufferTooSmall { needed, capacity: values.len() });
+        }
+        // every offset reached through `imap` is in `values`
+        unsafe { T::put_mapped_slice(self, indices, slice_len, imap, values.as_ptr().add(first)) }
+    }
+
+    /// Fetchs variable values as a column major (Fortran order) ndarray.
+    pub fn as_array_fortran<T: Numeric>(&self) -> Result<ArrayD<T>> {
+        let slice_len: Vec<usize> = self.dimensions.iter().map(|d| d.len() as usize).collect();
+        self.array_at_fortran(&vec![0; slice_len.len()], &slice_len)
+    }
+
+    /// Fetchs variable slice as a column major (Fortran order) ndarray.
+    pub fn array_at_fortran<T: Numeric>(&self, indices: &[usize], slice_len: &[usize]) -> Result<ArrayD<T>> {
+        let values = self.values_mapped(indices, slice_len, &fortran_imap(slice_len))?;
+        Ok(ArrayD::<T>::from_shape_vec(slice_len.f(), values)?)
+    }
+
     /// Put a single value at `indices`
-    pub fn put_value_at<T: Numeric>(&mut self, value: T, indices: &[usize]) -> Result<(), String> {
+    pub fn put_value_at<T: Numeric>(&mut self, value: T, indices: &[usize]) -> Result<()> {
         T::put_value_at(self, indices, value)
     }
 
-    /// Put a slice of values at `indices`
-    pub fn put_values_at<T: Numeric>(&mut self, values: &[T], indices: &[usize], slice_len: &[usize]) -> Result<(), String> {
+    /// Put a slice of values at `indices`, unlimited dimensions grow to fit the values.
+    pub fn put_values_at<T: Numeric>(&mut self, values: &[T], indices: &[usize], slice_len: &[usize]) -> Result<()> {
         T::put_values_at(self, indices, slice_len, values)
     }
 
+    /// Put a strided slice of values at `indices`, see `values_strided`
+    pub fn put_values_strided<T: Numeric>(&mut self, values: &[T], indices: &[usize], slice_len: &[usize], stride: &[usize]) -> Result<()> {
+        T::put_values_strided(self, indices, slice_len, stride, values)
+    }
+
+    /// Fetchs the values of a NC_STRING variable
+    ///
+    /// Strings are not required to be UTF-8, invalid sequences are replaced by U+FFFD.
+    pub fn get_strings(&self) -> Result<Vec<String>> {
+        if self.vartype != NC_STRING {
+            return Err(Error::TypeMismatch { expected: NC_STRING, found: self.vartype });
+        }
+        let mut ptrs: Vec<*mut libc::c_char> = vec![ptr::null_mut(); self.len() as usize];
+        let strings: Vec<String>;
+        data_mode(self.grp_id)?;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            let err = nc_get_var_string(self.grp_id, self.id, ptrs.as_mut_ptr());
+            if err != NC_NOERR {
+                return Err(Error::netcdf(err, "nc_get_var_string", self.name.clone()));
+            }
+            // copy the strings before handing the memory back to libnetcdf
+            strings = ptrs.iter().map(|p| {
+                if p.is_null() {
+                    String::new()
+                } else {
+                    ffi::CStr::from_ptr(*p).to_string_lossy().into_owned()
+                }
+            }).collect();
+            nc_free_string(ptrs.len() as u64, ptrs.as_mut_ptr());
+        }
+        Ok(strings)
+    }
+
+    /// Put a slice of strings at `indices` into a NC_STRING variable
+    pub fn put_strings_at<S: AsRef<str>>(&mut self, values: &[S], indices: &[usize], slice_len: &[usize]) -> Result<()> {
+        let values_len = check_slice(self, indices, slice_len, None, true)?;
+        if values_len != values.len() {
+            return Err(Error::ValuesLen { expected: values_len, found: values.len() });
+        }
+        let values_c: Vec<ffi::CString> = values.iter()
+            .map(|v| c_string(v.as_ref()))
+            .collect::<Result<_>>()?;
+        let mut ptrs: Vec<*const libc::c_char> = values_c.iter().map(|v| v.as_ptr()).collect();
+        let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
+        let slice: Vec<size_t> = slice_len.iter().map(|i| *i as size_t).collect();
+        let err: i32;
+        data_mode(self.grp_id)?;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_put_vara_string(self.grp_id, self.id, indices.as_ptr(), slice.as_ptr(),
+                                     ptrs.as_mut_ptr());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_put_vara_string", self.name.clone()));
+        }
+        Ok(())
+    }
+
+    /// Length of the strings stored in a NC_CHAR variable,
+    /// i.e. the length of its last dimension.
+    fn char_strlen(&self) -> Result<usize> {
+        if self.vartype != NC_CHAR {
+            return Err(Error::TypeMismatch { expected: NC_CHAR, found: self.vartype });
+        }
+        Ok(self.dimensions.last().map(|d| d.len() as usize).unwrap_or(1))
+    }
+
+    /// Fetchs the values of a NC_CHAR variable as strings, the last dimension
+    /// being the string length. Trailing NUL and space padding is removed.
+    pub fn get_char_strings(&self) -> Result<Vec<String>> {
+        let strlen = self.char_strlen()?;
+        let mut buf: Vec<libc::c_char> = vec![0; self.len() as usize];
+        let err: i32;
+        data_mode(self.grp_id)?;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_get_var_text(self.grp_id, self.id, buf.as_mut_ptr());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_get_var_text", self.name.clone()));
+        }
+        if strlen == 0 {
+            return Ok(Vec::new());
+        }
+        let bytes: Vec<u8> = buf.iter().map(|c| *c as u8).collect();
+        Ok(bytes.chunks(strlen)
+            .map(|chunk| {
+                String::from_utf8_lossy(chunk)
+                    .trim_end_matches(&['\0', ' '][..])
+                    .to_string()
+            })
+            .collect())
+    }
+
+    /// Put strings into a NC_CHAR variable, the last dimension being the string length.
+    /// `indices` and `slice_len` cover all the other dimensions;
+    /// each string is padded with NUL up to the string length.
+    pub fn put_char_strings_at<S: AsRef<str>>(&mut self, values: &[S], indices: &[usize], slice_len: &[usize]) -> Result<()> {
+        let strlen = self.char_strlen()?;
+        let mut indices = indices.to_vec();
+        let mut slice_len = slice_len.to_vec();
+        if !self.dimensions.is_empty() {
+            indices.push(0);
+            slice_len.push(strlen);
+        }
+        let values_len = check_slice(self, &indices, &slice_len, None, true)? / strlen.max(1);
+        if values_len != values.len() {
+            return Err(Error::ValuesLen { expected: values_len, found: values.len() });
+        }
+        let mut buf: Vec<libc::c_char> = Vec::with_capacity(values_len * strlen);
+        for value in values {
+            let value = value.as_ref().as_bytes();
+            if value.len() > strlen {
+                return Err(Error::StringTooLong { len: value.len(), max: strlen });
+            }
+            buf.extend(value.iter().map(|b| *b as libc::c_char));
+            let padded_len = buf.len() + strlen - value.len();
+            buf.resize(padded_len, 0);
+        }
+        let indices: Vec<size_t> = indices.iter().map(|i| *i as size_t).collect();
+        let slice: Vec<size_t> = slice_len.iter().map(|i| *i as size_t).collect();
+        let err: i32;
+        data_mode(self.grp_id)?;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_put_vara_text(self.grp_id, self.id, indices.as_ptr(), slice.as_ptr(),
+                                   buf.as_ptr());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_put_vara_text", self.name.clone()));
+        }
+        Ok(())
+    }
+
+    /// Storage layout of the variable.
+    pub fn chunking(&self) -> Result<Chunking> {
+        let mut storage: i32 = 0;
+        let mut sizes: Vec<size_t> = vec![0; self.dimensions.len()];
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_inq_var_chunking(self.grp_id, self.id, &mut storage, sizes.as_mut_ptr());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_var_chunking", self.name.clone()));
+        }
+        match storage {
+            NC_CHUNKED => Ok(Chunking::Chunked(sizes.iter().map(|s| *s as usize).collect())),
+            NC_COMPACT => Ok(Chunking::Compact),
+            _ => Ok(Chunking::Contiguous),
+        }
+    }
+
+    /// Compression settings of the variable.
+    pub fn compression(&self) -> Result<Compression> {
+        let mut shuffle: i32 = 0;
+        let mut deflate: i32 = 0;
+        let mut deflate_level: i32 = 0;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_inq_var_deflate(self.grp_id, self.id,
+                                     &mut shuffle, &mut deflate, &mut deflate_level);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_var_deflate", self.name.clone()));
+        }
+        Ok(Compression {
+            shuffle: shuffle != NC_NOSHUFFLE,
+            deflate_level: if deflate != 0 { Some(deflate_level) } else { None },
+        })
+    }
+
+    /// Returns `true` if a Fletcher32 checksum is stored with the data.
+    pub fn fletcher32(&self) -> Result<bool> {
+        let mut fletcher32: i32 = 0;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_inq_var_fletcher32(self.grp_id, self.id, &mut fletcher32);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_var_fletcher32", self.name.clone()));
+        }
+        Ok(fletcher32 == NC_FLETCHER32)
+    }
+
+    /// Byte order of the data stored on disk.
+    pub fn endianness(&self) -> Result<Endianness> {
+        let mut endian: i32 = 0;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_inq_var_endian(self.grp_id, self.id, &mut endian);
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_var_endian", self.name.clone()));
+        }
+        Ok(Endianness::from_nc_endian(endian))
+    }
+
     /// Set a Fill Value
-    pub fn set_fill_value<T: Numeric>(&mut self, fill_value: T) -> Result<(), String> {
+    pub fn set_fill_value<T: Numeric>(&mut self, fill_value: T) -> Result<()> {
+        define_mode(self.grp_id)?;
         let err: i32;
         unsafe {
             let _g = libnetcdf_lock.lock().unwrap();
             err = nc_def_var_fill(self.grp_id, self.id, 0 as libc::c_int, fill_value.as_void_ptr());
         }
         if err != NC_NOERR {
-            return Err(NC_ERRORS.get(&err).unwrap().clone());
+            return Err(Error::netcdf(err, "nc_def_var_fill", self.name.clone()));
         }
         self.update_attributes()?;
         Ok(())
     }
 
+    /// Disable filling: unwritten values are left undefined,
+    /// and no default fill value is used to flag missing values
+    pub fn set_no_fill(&mut self) -> Result<()> {
+        define_mode(self.grp_id)?;
+        let err: i32;
+        unsafe {
+            let _g = libnetcdf_lock.lock().unwrap();
+            err = nc_def_var_fill(self.grp_id, self.id, 1 as libc::c_int, ptr::null());
+        }
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_def_var_fill", self.name.clone()));
+        }
+        Ok(())
+    }
+
     /// update self.attributes, (sync cached attribute and the file)
-    fn update_attributes(&mut self) -> Result<(), String> {
+    fn update_attributes(&mut self) -> Result<()> {
         let mut natts: i32 = 0;
         let err: i32;
         unsafe {
@@ -571,63 +1470,71 @@ impl Variable {
             err = nc_inq_varnatts(self.grp_id, self.id, &mut natts);
         }
         if err != NC_NOERR {
-            return Err(NC_ERRORS.get(&err).unwrap().clone());
+            return Err(Error::netcdf(err, "nc_inq_varnatts", self.name.clone()));
         }
         let (grp_id, var_id) = (self.grp_id, self.id);
         self.attributes.clear();
-        init_attributes(&mut self.attributes, grp_id, var_id, natts);
-        Ok(())
+        init_attributes(&mut self.attributes, grp_id, var_id, natts)
     }
 }
 
-pub fn init_variables(vars: &mut HashMap<String, Variable>, grp_id: i32, grp_dims: &HashMap<String, Dimension>) {
+pub fn init_variables(vars: &mut HashMap<String, Variable>, grp_id: i32, grp_dims: &HashMap<String, Dimension>) -> Result<()> {
     // determine number of vars
     let mut nvars = 0i32;
+    let err: i32;
     unsafe {
         let _g = libnetcdf_lock.lock().unwrap();
-        let err = nc_inq_nvars(grp_id, &mut nvars);
-        assert_eq!(err, NC_NOERR);
+        err = nc_inq_nvars(grp_id, &mut nvars);
+    }
+    if err != NC_NOERR {
+        return Err(Error::netcdf(err, "nc_inq_nvars", format!("ncid {}", grp_id)));
     }
     for i_var in 0..nvars {
-        init_variable(vars, grp_id, grp_dims, i_var);
+        init_variable(vars, grp_id, grp_dims, i_var)?;
     }
+    Ok(())
 }
 
 /// Creates and add a `Variable` Objects, from the dataset
-pub fn init_variable(vars: &mut HashMap<String, Variable>, grp_id: i32, grp_dims: &HashMap<String, Dimension>, varid: i32) {
+pub fn init_variable(vars: &mut HashMap<String, Variable>, grp_id: i32, grp_dims: &HashMap<String, Dimension>, varid: i32) -> Result<()> {
     // read each dim name and length
     let mut buf_vec = vec![0i8; (NC_MAX_NAME + 1) as usize];
     let c_str: &ffi::CStr;
     let mut var_type : i32 = 0;
     let mut ndims : i32 = 0;
-    let mut dimids : Vec<i32> = Vec::with_capacity(NC_MAX_DIMS as usize);
+    let mut dimids : Vec<i32>;
     let mut natts : i32 = 0;
     unsafe {
         let _g = libnetcdf_lock.lock().unwrap();
         let buf_ptr : *mut i8 = buf_vec.as_mut_ptr();
         let err = nc_inq_var(grp_id, varid, buf_ptr,
                                 &mut var_type, &mut ndims,
-                                dimids.as_mut_ptr(), &mut natts);
-        dimids.set_len(ndims as usize);
-        assert_eq!(err, NC_NOERR);
+                                ptr::null_mut(), &mut natts);
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_var", format!("varid {}", varid)));
+        }
         c_str = ffi::CStr::from_ptr(buf_ptr);
+        dimids = vec![0i32; ndims as usize];
+        let err = nc_inq_vardimid(grp_id, varid, dimids.as_mut_ptr());
+        if err != NC_NOERR {
+            return Err(Error::netcdf(err, "nc_inq_vardimid", c_str.to_string_lossy().into_owned()));
+        }
     }
-    let str_buf: String = string_from_c_str(c_str);
+    let str_buf: String = string_from_c_str(c_str)?;
     let mut attr_map : HashMap<String, Attribute> = HashMap::new();
-    init_attributes(&mut attr_map, grp_id, varid, natts);
-    // var dims should always be a subset of the group dims:
+    init_attributes(&mut attr_map, grp_id, varid, natts)?;
     let mut dim_vec : Vec<Dimension> = Vec::new();
-    let mut len : u64 = 1;
     for dimid in dimids {
         // maintaining dim order is crucial here so we can maintain
         // rule that "last dim varies fastest" in our 1D return Vec
-        for (_, grp_dim) in grp_dims {
-            if dimid == grp_dim.id {
-                len *= grp_dim.len;
-                dim_vec.push(grp_dim.clone());
-                break
-            }
-        }
+        //
+        // Dimensions are matched on their dimid, not their name: a group
+        // dimension may shadow a parent dimension still used by the variable.
+        let dim = match grp_dims.values().find(|d| d.id == dimid) {
+            Some(d) => d.clone(),
+            None => find_dimension(grp_id, dimid)?,
+        };
+        dim_vec.push(dim);
     }
     vars.insert(
         str_buf.clone(),
@@ -636,10 +1543,9 @@ pub fn init_variable(vars: &mut HashMap<String, Variable>, grp_id: i32, grp_dims
             attributes: attr_map,
             dimensions: dim_vec,
             vartype: var_type,
-            len: len,
             id: varid,
-            grp_id: grp_id
+            grp_id,
         }
    );
+    Ok(())
 }
-
@@ -1,15 +1,47 @@
 extern crate netcdf;
+extern crate netcdf_sys;
 
+#[macro_use]
 extern crate ndarray;
 use ndarray::ArrayD;
 use netcdf::{test_file, test_file_new};
 
 // Failure tests
 #[test]
-#[should_panic(expected = "No such file or directory")]
 fn bad_filename() {
     let f = test_file("blah_stuff.nc");
-    let _file = netcdf::open(&f).unwrap();
+    let err = netcdf::open(&f).err().unwrap();
+    // libnetcdf forwards the system errno (ENOENT) for missing files
+    assert_eq!(err.code(), Some(2));
+    assert!(err.to_string().contains("No such file or directory"));
+}
+
+#[test]
+fn not_a_netcdf_file() {
+    let f = test_file_new("not_a_netcdf_file.nc");
+    std::fs::write(&f, b"definitely not netCDF").unwrap();
+    let err = netcdf::open(&f).err().unwrap();
+    assert!(err.code().is_some());
+}
+
+#[test]
+fn out_of_range_errors() {
+    let f = test_file("simple_xy.nc");
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("data").unwrap();
+
+    assert_eq!(
+        var.value_at::<i32>(&[6, 0]),
+        Err(netcdf::Error::IndexOutOfRange { dim: 0, index: 6, len: 6 })
+    );
+    assert_eq!(
+        var.values_at::<i32>(&[0], &[1]),
+        Err(netcdf::Error::IndexLen { expected: 2, found: 1 })
+    );
+    assert_eq!(
+        var.values_at::<i32>(&[0, 10], &[1, 5]),
+        Err(netcdf::Error::SliceOutOfRange { dim: 1, start: 10, count: 5, len: 12 })
+    );
 }
 
 // Read tests
@@ -20,8 +52,8 @@ fn root_dims() {
     let file = netcdf::open(&f).unwrap();
     assert_eq!(f, file.name);
 
-    assert_eq!(file.root.dimensions.get("x").unwrap().len, 6);
-    assert_eq!(file.root.dimensions.get("y").unwrap().len, 12);
+    assert_eq!(file.root.dimensions.get("x").unwrap().len(), 6);
+    assert_eq!(file.root.dimensions.get("y").unwrap().len(), 12);
 }
 
 #[test]
@@ -107,7 +139,7 @@ fn implicit_cast() {
 }
 
 #[test]
-#[should_panic(expected = "Types are not equivalent and cast==false")]
+#[should_panic(expected = "TypeMismatch")]
 fn var_cast_fail() {
     let f = test_file("simple_xy.nc");
 
@@ -128,12 +160,12 @@ fn last_dim_varies_fastest() {
     let var = file.root.variables.get("data").unwrap();
     let data : Vec<i32> = var.get_int(false).unwrap();
 
-    let nx = var.dimensions[0].len;
-    let ny = var.dimensions[1].len;
+    let nx = var.dimensions[0].len();
+    let ny = var.dimensions[1].len();
 
     assert_eq!(nx, 6);
     assert_eq!(ny, 12);
-    assert_eq!(nx*ny, var.len);
+    assert_eq!(nx*ny, var.len());
 
     for x in 0..nx {
         for y in 0..ny {
@@ -187,6 +219,19 @@ fn create() {
     assert_eq!(f, file.name);
 }
 
+#[test]
+fn explicit_close() {
+    let f = test_file_new("explicit_close.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("x", 4).unwrap();
+        file.close().unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    assert_eq!(file.root.dimensions.get("x").unwrap().len(), 4);
+    file.close().unwrap();
+}
+
 #[test]
 fn def_dims_vars_attrs() {
     {
@@ -198,8 +243,8 @@ fn def_dims_vars_attrs() {
         let dim2_name = "dsfkdfskl";
         file.root.add_dimension(dim1_name, 10).unwrap();
         file.root.add_dimension(dim2_name, 20).unwrap();
-        assert_eq!(file.root.dimensions.get(dim1_name).unwrap().len, 10);
-        assert_eq!(file.root.dimensions.get(dim2_name).unwrap().len, 20);
+        assert_eq!(file.root.dimensions.get(dim1_name).unwrap().len(), 10);
+        assert_eq!(file.root.dimensions.get(dim2_name).unwrap().len(), 20);
 
         let var_name = "varstuff_int";
         let data : Vec<i32> = vec![42; (10*20)];
@@ -208,7 +253,7 @@ fn def_dims_vars_attrs() {
                     &vec![dim1_name.to_string(), dim2_name.to_string()],
                     &data
                 ).unwrap();
-        assert_eq!(file.root.variables.get(var_name).unwrap().len, 20*10);
+        assert_eq!(file.root.variables.get(var_name).unwrap().len(), 20*10);
 
         let var_name = "varstuff_float";
         let data : Vec<f32> = vec![42.2; 10];
@@ -217,7 +262,7 @@ fn def_dims_vars_attrs() {
                     &vec![dim1_name.to_string()],
                     &data
                 ).unwrap();
-        assert_eq!(file.root.variables.get(var_name).unwrap().len, 10);
+        assert_eq!(file.root.variables.get(var_name).unwrap().len(), 10);
 
         // test global attrs
         file.root.add_attribute(
@@ -251,8 +296,8 @@ fn def_dims_vars_attrs() {
         let dim2_name = "dsfkdfskl";
         let dim1 = file.root.dimensions.get(dim1_name).unwrap();
         let dim2 = file.root.dimensions.get(dim2_name).unwrap();
-        assert_eq!(dim1.len, 10);
-        assert_eq!(dim2.len, 20);
+        assert_eq!(dim1.len(), 10);
+        assert_eq!(dim2.len(), 20);
 
         // verify variable data
         let var_name = "varstuff_int";
@@ -290,6 +335,78 @@ fn def_dims_vars_attrs() {
     }
 }
 
+#[test]
+fn create_groups() {
+    let f = test_file_new("create_groups.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("x", 3).unwrap();
+        file.root.add_group("geolocation").unwrap();
+        {
+            let science = file.root.add_group("science").unwrap();
+            let level2 = science.add_group("level2").unwrap();
+            level2.add_dimension("y", 2).unwrap();
+            level2.add_variable(
+                "data",
+                &["x".to_string(), "y".to_string()],
+                &vec![1i32, 2, 3, 4, 5, 6]
+            ).unwrap();
+            level2.add_attribute("level", 2i32).unwrap();
+        }
+        // dimensions added to the parent afterwards are visible in the sub-groups
+        file.root.add_dimension("t", 4).unwrap();
+        assert_eq!(file.group("science/level2").unwrap().dimensions.get("t").unwrap().len(), 4);
+        {
+            let geolocation = file.root.sub_groups.get_mut("geolocation").unwrap();
+            assert!(geolocation.dimensions.contains_key("t"));
+            geolocation.add_variable("time", &["t".to_string()], &vec![0i32, 1, 2, 3]).unwrap();
+        }
+        assert!(file.group("science/level2").is_some());
+        assert!(file.group("/science/level3").is_none());
+    }
+    let file = netcdf::open(&f).unwrap();
+    assert!(file.group("geolocation").is_some());
+    let level2 = file.group("/science/level2").unwrap();
+    assert_eq!(level2.name, "level2");
+    assert_eq!(level2.attributes.get("level").unwrap().get_int(false).unwrap(), 2);
+    let var = level2.variables.get("data").unwrap();
+    assert_eq!(var.values::<i32>().unwrap(), vec![1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn nested_dimension_scoping() {
+    let f = test_file_new("nested_dimension_scoping.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("x", 3).unwrap();
+        let grp = file.root.add_group("grp").unwrap();
+        grp.add_dimension("y", 2).unwrap();
+        // uses the root "x"
+        grp.add_variable(
+            "data",
+            &["x".to_string(), "y".to_string()],
+            &vec![1i32, 2, 3, 4, 5, 6]
+        ).unwrap();
+        // shadows the root "x" for anything defined afterwards
+        grp.add_dimension("x", 5).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let grp = file.group("grp").unwrap();
+    let root_x = file.root.dimensions.get("x").unwrap();
+    let grp_x = grp.dimensions.get("x").unwrap();
+    assert_eq!(grp_x.len(), 5);
+    assert_eq!(grp_x.grp_id, grp.id);
+    assert!(grp_x.id != root_x.id);
+
+    let var = grp.variables.get("data").unwrap();
+    assert_eq!(var.dimensions.len(), 2);
+    assert_eq!(var.dimensions[0].id, root_x.id);
+    assert_eq!(var.dimensions[0].len(), 3);
+    assert_eq!(var.dimensions[0].grp_id, file.root.id);
+    assert_eq!(var.dimensions[1].grp_id, grp.id);
+    assert_eq!(var.len(), 6);
+}
+
 #[test]
 fn all_var_types() {
     // write
@@ -526,6 +643,840 @@ fn all_attr_types() {
     }
 }
 
+#[test]
+fn multi_valued_attrs() {
+    let f = test_file_new("multi_valued_attrs.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("x", 2).unwrap();
+        file.root.add_variable("flags", &["x".into()], &vec![1i8, 2]).unwrap();
+        file.root.add_attribute("actual_range", vec![-1.5f64, 30.25]).unwrap();
+        let var = file.root.variables.get_mut("flags").unwrap();
+        var.add_attribute("valid_range", &[0i8, 4][..]).unwrap();
+        var.add_attribute("flag_values", vec![1i8, 2, 4]).unwrap();
+        var.add_attribute("flag_weights", vec![0.5f32, 1., 2.]).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let range = file.root.attributes.get("actual_range").unwrap();
+    assert_eq!(range.values::<f64>().unwrap(), vec![-1.5, 30.25]);
+    // implicit cast
+    assert_eq!(range.values::<f32>().unwrap(), vec![-1.5f32, 30.25]);
+    // scalar getters refuse multi-valued attributes
+    assert_eq!(
+        range.get_double(false),
+        Err(netcdf::Error::AttributeNotScalar { name: "actual_range".into(), len: 2 })
+    );
+
+    let var = file.root.variables.get("flags").unwrap();
+    let valid_range = var.attributes.get("valid_range").unwrap();
+    assert_eq!(valid_range.values::<i8>().unwrap(), vec![0, 4]);
+    let flag_values = var.attributes.get("flag_values").unwrap();
+    assert_eq!(flag_values.values::<i32>().unwrap(), vec![1, 2, 4]);
+    let flag_weights = var.attributes.get("flag_weights").unwrap();
+    assert_eq!(flag_weights.values::<f64>().unwrap(), vec![0.5, 1., 2.]);
+}
+
+#[test]
+fn attr_value() {
+    use netcdf::attribute::AttributeValue;
+    let f = test_file_new("attr_value.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_attribute("title", "Say \"hi\"".to_string()).unwrap();
+        file.root.add_attribute("flag_values", vec![1i16, 2]).unwrap();
+        file.root.add_attribute("scale_factor", 0.5f32).unwrap();
+        file.root.add_attribute("add_offset", vec![0f64, 2.25]).unwrap();
+        file.root.add_attribute("count", 3u8).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let attr = |name: &str| file.root.attributes.get(name).unwrap();
+
+    assert_eq!(attr("title").value().unwrap(), AttributeValue::Str("Say \"hi\"".into()));
+    assert_eq!(attr("flag_values").value().unwrap(), AttributeValue::Shorts(vec![1, 2]));
+    assert_eq!(attr("scale_factor").value().unwrap(), AttributeValue::Floats(vec![0.5]));
+    assert_eq!(attr("count").value().unwrap(), AttributeValue::U8s(vec![3]));
+
+    // ncdump-like formatting
+    assert_eq!(attr("title").to_string(), "\"Say \\\"hi\\\"\"");
+    assert_eq!(attr("flag_values").to_string(), "1s, 2s");
+    assert_eq!(attr("scale_factor").to_string(), "0.5f");
+    assert_eq!(attr("add_offset").to_string(), "0., 2.25");
+    assert_eq!(attr("count").to_string(), "3ub");
+}
+
+#[test]
+fn string_variables() {
+    let f = test_file_new("string_variables.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("station", 3).unwrap();
+        let names = vec!["Boulder".to_string(), "".to_string(), "Zürich".to_string()];
+        file.root.add_variable("name", &["station".to_string()], &names).unwrap();
+        file.root.add_attribute("sources", vec!["a".to_string(), "bc".to_string()]).unwrap();
+
+        let var = file.root.variables.get_mut("name").unwrap();
+        var.put_strings_at(&["Denver"], &[1], &[1]).unwrap();
+        assert!(var.put_strings_at(&["x", "y"], &[2], &[2]).is_err());
+        let nul = netcdf::Error::InteriorNul("a\0b".to_string());
+        assert_eq!(var.put_strings_at(&["a\0b"], &[0], &[1]), Err(nul.clone()));
+        assert_eq!(file.root.add_attribute("bad", "a\0b".to_string()), Err(nul.clone()));
+        assert_eq!(file.root.add_attribute("bad", vec!["a\0b".to_string()]), Err(nul));
+        assert!(!file.root.attributes.contains_key("bad"));
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("name").unwrap();
+    assert_eq!(var.vartype, netcdf_sys::NC_STRING);
+    assert_eq!(var.get_strings().unwrap(), vec!["Boulder", "Denver", "Zürich"]);
+    assert_eq!(
+        file.root.attributes.get("sources").unwrap().get_strings().unwrap(),
+        vec!["a", "bc"]);
+}
+
+#[test]
+fn char_array_strings() {
+    let f = test_file_new("char_array_strings.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("station", 3).unwrap();
+        file.root.add_dimension("name_strlen", 8).unwrap();
+        let dims = vec!["station".to_string(), "name_strlen".to_string()];
+        let var = file.root.create_variable("station_name", &dims, netcdf_sys::NC_CHAR).unwrap();
+        var.put_char_strings_at(&["Boulder", "Denver"], &[0], &[2]).unwrap();
+        var.put_char_strings_at(&["Lyon"], &[2], &[1]).unwrap();
+        match var.put_char_strings_at(&["Albuquerque"], &[2], &[1]) {
+            Err(netcdf::Error::StringTooLong { len: 11, max: 8 }) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("station_name").unwrap();
+    assert_eq!(var.get_char_strings().unwrap(), vec!["Boulder", "Denver", "Lyon"]);
+}
+
+#[test]
+fn create_formats() {
+    use netcdf::file::{Format, Options};
+    let formats = [
+        (Format::Classic, "format_classic.nc"),
+        (Format::Offset64, "format_offset64.nc"),
+        (Format::Cdf5, "format_cdf5.nc"),
+        (Format::Netcdf4, "format_netcdf4.nc"),
+        (Format::Netcdf4Classic, "format_netcdf4_classic.nc"),
+    ];
+    for &(format, name) in formats.iter() {
+        let f = test_file_new(name);
+        {
+            let mut file = netcdf::create_with(&f, Options::new().format(format)).unwrap();
+            assert_eq!(file.format().unwrap(), format);
+            file.root.add_dimension("x", 3).unwrap();
+            file.root.add_variable("v", &["x".to_string()], &vec![1i32, 2, 3]).unwrap();
+            // back to define mode after writing values
+            file.root.add_dimension("y", 2).unwrap();
+            file.root.add_attribute("title", "formats".to_string()).unwrap();
+            let w = file.root.create_variable("w", &["y".to_string()], netcdf_sys::NC_DOUBLE).unwrap();
+            w.add_attribute("units", "m".to_string()).unwrap();
+            w.put_values_at(&[0.5, 1.5], &[0], &[2]).unwrap();
+            assert_eq!(w.values::<f64>().unwrap(), vec![0.5, 1.5]);
+
+            // storage options are rejected before defining the variable
+            let options = netcdf::variable::VariableOptions::new().shuffle(true);
+            let created = file.root.create_variable_with("s", &["x".to_string()], netcdf_sys::NC_INT, &options);
+            if matches!(format, Format::Netcdf4 | Format::Netcdf4Classic) {
+                created.unwrap();
+            } else {
+                assert_eq!(created.err(), Some(netcdf::Error::NotNetcdf4("shuffle")));
+                assert!(!file.root.variables.contains_key("s"));
+            }
+        }
+        let file = netcdf::open(&f).unwrap();
+        assert_eq!(file.format().unwrap(), format);
+        assert_eq!(file.root.variables.get("v").unwrap().values::<i32>().unwrap(), vec![1, 2, 3]);
+        assert_eq!(file.root.variables.get("w").unwrap().values::<f64>().unwrap(), vec![0.5, 1.5]);
+
+        // the file exists now
+        let err = netcdf::create_with(&f, Options::new().format(format).noclobber(true));
+        assert_eq!(err.err().and_then(|e| e.code()), Some(netcdf_sys::NC_EEXIST));
+    }
+}
+
+#[test]
+fn variable_chunking() {
+    use netcdf::variable::{Chunking, VariableOptions};
+    let f = test_file_new("variable_chunking.nc");
+    let dims = vec!["time".to_string(), "y".to_string(), "x".to_string()];
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("time", 10).unwrap();
+        file.root.add_dimension("y", 20).unwrap();
+        file.root.add_dimension("x", 30).unwrap();
+        let chunked = VariableOptions::new().chunking(Chunking::Chunked(vec![10, 1, 1]));
+        file.root.create_variable_with("chunked", &dims, netcdf_sys::NC_FLOAT, &chunked).unwrap();
+        let contiguous = VariableOptions::new().chunking(Chunking::Contiguous);
+        file.root.create_variable_with("contiguous", &dims, netcdf_sys::NC_FLOAT, &contiguous).unwrap();
+
+        let bad = VariableOptions::new().chunking(Chunking::Chunked(vec![10, 1]));
+        match file.root.create_variable_with("bad", &dims, netcdf_sys::NC_FLOAT, &bad) {
+            Err(netcdf::Error::ChunkLen { expected: 3, found: 2 }) => {}
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("chunk sizes should have been rejected"),
+        }
+        let bad = VariableOptions::new().deflate(10);
+        match file.root.create_variable_with("bad", &dims, netcdf_sys::NC_FLOAT, &bad) {
+            Err(netcdf::Error::DeflateLevel(10)) => {}
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("deflate level should have been rejected"),
+        }
+        let bad = VariableOptions::new().chunking(Chunking::Chunked(vec![0, 1, 1]));
+        assert_eq!(file.root.create_variable_with("bad", &dims, netcdf_sys::NC_FLOAT, &bad).err(),
+                   Some(netcdf::Error::ChunkSize { dim: 0, size: 0, len: 10 }));
+        let bad = VariableOptions::new().chunking(Chunking::Chunked(vec![10, 21, 1]));
+        assert_eq!(file.root.create_variable_with("bad", &dims, netcdf_sys::NC_FLOAT, &bad).err(),
+                   Some(netcdf::Error::ChunkSize { dim: 1, size: 21, len: 20 }));
+        let bad = VariableOptions::new().chunking(Chunking::Contiguous).deflate(4);
+        assert_eq!(file.root.create_variable_with("bad", &dims, netcdf_sys::NC_FLOAT, &bad).err(),
+                   Some(netcdf::Error::NotChunked("deflate".to_string())));
+        file.root.add_unlimited_dimension("record").unwrap();
+        let bad = VariableOptions::new().chunking(Chunking::Contiguous);
+        assert_eq!(file.root.create_variable_with("bad", &["record".to_string()], netcdf_sys::NC_FLOAT, &bad).err(),
+                   Some(netcdf::Error::NotChunked("the unlimited dimension 'record'".to_string())));
+        // rejected before being defined
+        assert!(!file.root.variables.contains_key("bad"));
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = |name: &str| file.root.variables.get(name).unwrap();
+    assert_eq!(var("chunked").chunking().unwrap(), Chunking::Chunked(vec![10, 1, 1]));
+    assert_eq!(var("contiguous").chunking().unwrap(), Chunking::Contiguous);
+    assert!(!file.root.variables.contains_key("bad"));
+}
+
+#[test]
+fn variable_compression() {
+    use netcdf::variable::{Compression, VariableOptions};
+    let f = test_file_new("variable_compression.nc");
+    let dims = vec!["y".to_string(), "x".to_string()];
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("y", 100).unwrap();
+        file.root.add_dimension("x", 100).unwrap();
+        let options = VariableOptions::new().deflate(4).shuffle(true);
+        let var = file.root.create_variable_with("swath", &dims, netcdf_sys::NC_SHORT, &options).unwrap();
+        var.put_value_at(7i16, &[3, 4]).unwrap();
+        file.root.create_variable("raw", &dims, netcdf_sys::NC_SHORT).unwrap();
+        let options = VariableOptions::new().deflate(0);
+        file.root.create_variable_with("level0", &dims, netcdf_sys::NC_SHORT, &options).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = |name: &str| file.root.variables.get(name).unwrap();
+    assert_eq!(var("swath").compression().unwrap(),
+               Compression { shuffle: true, deflate_level: Some(4) });
+    assert_eq!(var("raw").compression().unwrap(),
+               Compression { shuffle: false, deflate_level: None });
+    assert_eq!(var("level0").compression().unwrap(),
+               Compression { shuffle: false, deflate_level: None });
+    assert_eq!(var("swath").value_at::<i16>(&[3, 4]).unwrap(), 7);
+}
+
+#[test]
+fn variable_checksum_and_endianness() {
+    use netcdf::variable::{Endianness, VariableOptions};
+    let f = test_file_new("variable_checksum_endianness.nc");
+    let dims = vec!["x".to_string()];
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("x", 4).unwrap();
+        let options = VariableOptions::new().fletcher32(true).endianness(Endianness::Big);
+        let var = file.root.create_variable_with("archived", &dims, netcdf_sys::NC_INT, &options).unwrap();
+        var.put_value_at(258i32, &[1]).unwrap();
+        let options = VariableOptions::new().endianness(Endianness::Little);
+        file.root.create_variable_with("little", &dims, netcdf_sys::NC_INT, &options).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = |name: &str| file.root.variables.get(name).unwrap();
+    assert!(var("archived").fletcher32().unwrap());
+    assert_eq!(var("archived").endianness().unwrap(), Endianness::Big);
+    assert_eq!(var("archived").value_at::<i32>(&[1]).unwrap(), 258);
+    assert!(!var("little").fletcher32().unwrap());
+    assert_eq!(var("little").endianness().unwrap(), Endianness::Little);
+}
+
+#[test]
+fn strided_values() {
+    let f = test_file_new("strided_values.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("y", 6).unwrap();
+        file.root.add_dimension("x", 8).unwrap();
+        let data: Vec<i32> = (0..48).collect();
+        file.root.add_variable("grid", &["y".to_string(), "x".to_string()], &data).unwrap();
+
+        let var = file.root.variables.get_mut("grid").unwrap();
+        // every other pixel of the last row
+        var.put_values_strided(&[-1i32; 4], &[5, 1], &[1, 4], &[1, 2]).unwrap();
+        match var.put_values_strided(&[-1i32; 4], &[5, 2], &[1, 4], &[1, 2]) {
+            Err(netcdf::Error::StridedSliceOutOfRange { dim: 1, .. }) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("grid").unwrap();
+    let thumbnail: Vec<i32> = var.values_strided(&[0, 0], &[3, 4], &[2, 2]).unwrap();
+    assert_eq!(thumbnail, vec![0, 2, 4, 6, 16, 18, 20, 22, 32, 34, 36, 38]);
+    let array: ArrayD<i32> = var.array_strided(&[4, 1], &[2, 4], &[1, 2]).unwrap();
+    assert_eq!(array.shape(), &[2, 4]);
+    assert_eq!(array.into_raw_vec(), vec![33, 35, 37, 39, -1, -1, -1, -1]);
+    assert!(var.values_strided::<i32>(&[0, 0], &[3, 4], &[2, 0]).is_err());
+}
+
+#[test]
+fn slice_syntax() {
+    let f = test_file_new("slice_syntax.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("y", 5).unwrap();
+        file.root.add_dimension("x", 7).unwrap();
+        let data: Vec<i32> = (0..35).collect();
+        file.root.add_variable("grid", &["y".to_string(), "x".to_string()], &data).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("grid").unwrap();
+    let full: ArrayD<i32> = var.as_array().unwrap();
+
+    let a: ArrayD<i32> = var.array_slice(s![1..4, 2..5]).unwrap();
+    assert_eq!(a, full.slice(s![1..4, 2..5]).to_owned().into_dyn());
+    // steps and negative indices
+    let a: ArrayD<i32> = var.array_slice(s![..;2, -3..]).unwrap();
+    assert_eq!(a, full.slice(s![..;2, -3..]).to_owned().into_dyn());
+    // negative steps
+    let a: ArrayD<i32> = var.array_slice(s![1..;-2, ..;-3]).unwrap();
+    assert_eq!(a, full.slice(s![1..;-2, ..;-3]).to_owned().into_dyn());
+    // single indices drop their dimension
+    let a: ArrayD<i32> = var.array_slice(s![-1, 1..6;2]).unwrap();
+    assert_eq!(a.shape(), &[3]);
+    assert_eq!(a, full.slice(s![-1, 1..6;2]).to_owned().into_dyn());
+    let a: ArrayD<i32> = var.array_slice(s![2, 3]).unwrap();
+    assert_eq!(a.ndim(), 0);
+    assert_eq!(a[[]], 17);
+    // missing trailing dimensions are read entirely
+    let a: ArrayD<i32> = var.array_slice(s![3]).unwrap();
+    assert_eq!(a.into_raw_vec(), (21..28).collect::<Vec<i32>>());
+
+    match var.array_slice::<i32, _>(s![5, ..]) {
+        Err(netcdf::Error::SliceIndexOutOfRange { dim: 0, index: 5, len: 5 }) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn write_ndarray() {
+    use ndarray::Array2;
+    let f = test_file_new("write_ndarray.nc");
+    let dims = vec!["y".to_string(), "x".to_string()];
+    let grid = Array2::from_shape_fn((3, 4), |(y, x)| (10 * y + x) as f32);
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        // dimensions are created from the array shape
+        file.root.add_variable_from_array("grid", &dims, &grid).unwrap();
+        match file.root.add_variable_from_array("transposed", &dims, &grid.t()) {
+            Err(netcdf::Error::DimensionLen { ref name, len: 3, found: 4 }) if name == "y" => {}
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("the dimensions length should have been checked"),
+        }
+
+        file.root.add_variable_from_array("bytes", &["x".to_string()],
+                                          &ndarray::arr1(&[1u8, 2, 3, 4])).unwrap();
+        let var = file.root.create_variable("copy", &dims, netcdf_sys::NC_FLOAT).unwrap();
+        // non contiguous view of the transposed array
+        let transposed = grid.t().to_owned();
+        var.put_array_at(&transposed.t(), &[0, 0]).unwrap();
+        // multi-dimensional slice
+        var.put_array_at(&Array2::<f32>::zeros((2, 2)), &[1, 1]).unwrap();
+        assert!(var.put_array_at(&Array2::<f32>::zeros((2, 2)), &[2, 0]).is_err());
+        assert!(var.put_array_at(&ndarray::arr1(&[0f32]), &[0, 0]).is_err());
+        // empty arrays write nothing, but are still checked against the variable
+        let empty = Array2::<f32>::zeros((0, 2));
+        var.put_array_at(&empty, &[1, 0]).unwrap();
+        assert_eq!(var.put_array_at(&empty, &[5, 0]),
+                   Err(netcdf::Error::IndexOutOfRange { dim: 0, index: 5, len: 3 }));
+        assert_eq!(var.put_array_at(&empty, &[0]),
+                   Err(netcdf::Error::IndexLen { expected: 2, found: 1 }));
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = |name: &str| file.root.variables.get(name).unwrap();
+    assert_eq!(file.root.dimensions.get("x").unwrap().len(), 4);
+    assert_eq!(var("grid").as_array::<f32>().unwrap(), grid.clone().into_dyn());
+    assert_eq!(var("bytes").vartype, netcdf_sys::NC_UBYTE);
+    let mut expected = grid.clone();
+    expected.slice_mut(s![1..3, 1..3]).fill(0.);
+    assert_eq!(var("copy").as_array::<f32>().unwrap(), expected.into_dyn());
+}
+
+#[test]
+fn read_into_ndarray() {
+    use ndarray::{Array2, Array3, ShapeBuilder};
+    let f = test_file_new("read_into_ndarray.nc");
+    let dims = vec!["t".to_string(), "y".to_string(), "x".to_string()];
+    let data = Array3::from_shape_fn((4, 5, 6), |(t, y, x)| (100 * t + 10 * y + x) as f32);
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_variable_from_array("data", &dims, &data).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("data").unwrap();
+
+    // preallocated tile, reused between reads
+    let mut tile = Array3::<f32>::zeros((2, 3, 3));
+    var.read_slice_into_array(&[1, 2, 3], &mut tile).unwrap();
+    assert_eq!(tile, data.slice(s![1..3, 2..5, 3..6]));
+    var.read_slice_into_array(&[2, 0, 0], &mut tile).unwrap();
+    assert_eq!(tile, data.slice(s![2..4, 0..3, 0..3]));
+
+    // strided view of a bigger array
+    let mut big = Array3::<f32>::zeros((4, 5, 6));
+    var.read_slice_into_array(&[0, 0, 0], &mut big.slice_mut(s![.., .., ..;2])).unwrap();
+    assert_eq!(big.slice(s![.., .., ..;2]), data.slice(s![.., .., ..3]));
+    assert_eq!(big.slice(s![.., .., 1..;2]), Array3::<f32>::zeros((4, 5, 3)));
+
+    // column major buffer
+    let mut fortran = Array3::<f32>::zeros((1, 5, 6).f());
+    var.read_slice_into_array(&[3, 0, 0], &mut fortran).unwrap();
+    assert_eq!(fortran, data.slice(s![3..4, .., ..]));
+
+    assert!(var.read_slice_into_array(&[3, 0, 0], &mut tile).is_err());
+    match var.read_slice_into_array(&[0, 0], &mut Array2::<f32>::zeros((2, 2))) {
+        Err(netcdf::Error::SliceLen { expected: 3, found: 2 }) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn mapped_io() {
+    use ndarray::{Array2, ShapeBuilder};
+    let f = test_file_new("mapped_io.nc");
+    let dims = vec!["y".to_string(), "x".to_string()];
+    let data = Array2::from_shape_fn((3, 4), |(y, x)| (10 * y + x) as i32);
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_variable_from_array("data", &dims, &data).unwrap();
+
+        // write a Fortran ordered array and a permuted view without copies
+        let fortran = Array2::from_shape_vec((3, 4).f(), (0..12).collect()).unwrap();
+        file.root.add_variable_from_array("fortran", &dims, &fortran).unwrap();
+        let permuted = Array2::from_shape_fn((4, 3), |(x, y)| (10 * y + x) as i32);
+        let var = file.root.create_variable("permuted", &dims, netcdf_sys::NC_INT).unwrap();
+        var.put_array_at(&permuted.t(), &[0, 0]).unwrap();
+        // reversed rows, through a negative imap
+        var.put_values_mapped(&[-1, -2, -3, -4], &[2, 0], &[1, 4], &[1, -1]).unwrap();
+        assert!(var.put_values_mapped(&[-1, -2, -3], &[2, 0], &[1, 4], &[1, 1]).is_err());
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = |name: &str| file.root.variables.get(name).unwrap();
+
+    let fortran: ArrayD<i32> = var("data").as_array_fortran().unwrap();
+    assert_eq!(fortran, data.clone().into_dyn());
+    assert_eq!(fortran.as_slice_memory_order().unwrap(),
+               &[0, 10, 20, 1, 11, 21, 2, 12, 22, 3, 13, 23]);
+    let column_major: Vec<i32> = var("data").values_mapped(&[1, 1], &[2, 3], &[1, 2]).unwrap();
+    assert_eq!(column_major, vec![11, 21, 12, 22, 13, 23]);
+
+    assert_eq!(var("fortran").values::<i32>().unwrap(),
+               vec![0, 3, 6, 9, 1, 4, 7, 10, 2, 5, 8, 11]);
+    let mut expected = data.clone();
+    expected.row_mut(2).assign(&ndarray::arr1(&[-4, -3, -2, -1]));
+    assert_eq!(var("permuted").as_array::<i32>().unwrap(), expected.into_dyn());
+}
+
+#[test]
+fn cf_packed_values() {
+    let f = test_file_new("cf_packed_values.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("x", 6).unwrap();
+        let var = file.root.create_variable("radiance", &["x".to_string()], netcdf_sys::NC_SHORT).unwrap();
+        var.add_attribute("scale_factor", 0.01f32).unwrap();
+        var.add_attribute("add_offset", 100f32).unwrap();
+        var.add_attribute("_FillValue", -32768i16).unwrap();
+        var.add_attribute("valid_range", vec![-10000i16, 10000]).unwrap();
+        var.put_values_encoded(&[100f64, 101.5, f64::NAN, 0.5, 50.], &[0], &[5]).unwrap();
+        // above valid_max once packed
+        var.put_value_at(20000i16, &[5]).unwrap();
+
+        let var = file.root.create_variable("no_fill", &["x".to_string()], netcdf_sys::NC_SHORT).unwrap();
+        var.set_no_fill().unwrap();
+        match var.put_values_encoded(&[f32::NAN], &[0], &[1]) {
+            Err(netcdf::Error::NoFillValue(ref name)) if name == "no_fill" => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("radiance").unwrap();
+    assert_eq!(var.values::<i16>().unwrap(), vec![0, 150, -32768, -9950, -5000, 20000]);
+
+    let decoded: Vec<f64> = var.values_decoded().unwrap();
+    let expected = [100., 101.5, f64::NAN, 0.5, 50., f64::NAN];
+    for (value, expected) in decoded.iter().zip(expected.iter()) {
+        assert!((value.is_nan() && expected.is_nan()) || (value - expected).abs() < 1e-4,
+                "{} != {}", value, expected);
+    }
+    let decoded: Vec<Option<f32>> = var.values_decoded_option().unwrap();
+    assert_eq!(decoded.iter().map(|v| v.is_some()).collect::<Vec<bool>>(),
+               vec![true, true, false, true, true, false]);
+    let array: ArrayD<f32> = var.as_array_decoded().unwrap();
+    assert_eq!(array.shape(), &[6]);
+    assert!((array[[1]] - 101.5).abs() < 1e-4);
+}
+
+#[test]
+fn masked_values() {
+    let f = test_file_new("masked_values.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("x", 6).unwrap();
+        let var = file.root.create_variable("counts", &["x".to_string()], netcdf_sys::NC_INT).unwrap();
+        var.add_attribute("missing_value", -1i32).unwrap();
+        var.add_attribute("valid_min", 0i32).unwrap();
+        var.add_attribute("valid_max", 100i32).unwrap();
+        // the last value is left unwritten, holding the default fill value
+        var.put_values_at(&[5i32, -1, 50, -3, 200], &[0], &[5]).unwrap();
+
+        let var = file.root.create_variable("temp", &["x".to_string()], netcdf_sys::NC_FLOAT).unwrap();
+        var.set_fill_value(-999f32).unwrap();
+        var.put_values_at(&[1f32, -999., 3., f32::NAN], &[0], &[4]).unwrap();
+
+        // the default fill value of NC_BYTE (-127) can't be read as u8
+        file.root.add_variable("flags", &["x".to_string()], &vec![0i8, 1, 0, 2, 0, 3]).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("counts").unwrap();
+    let (values, mask) = var.as_array_masked::<i32>().unwrap();
+    assert_eq!(values.as_slice().unwrap(), &[5, -1, 50, -3, 200, netcdf_sys::NC_FILL_INT]);
+    assert_eq!(mask.as_slice().unwrap(), &[false, true, false, true, true, true]);
+    let (values, mask) = var.array_at_masked::<i32>(&[1], &[3]).unwrap();
+    assert_eq!(values.as_slice().unwrap(), &[-1, 50, -3]);
+    assert_eq!(mask.as_slice().unwrap(), &[true, false, true]);
+
+    let var = file.root.variables.get("temp").unwrap();
+    let values: ArrayD<Option<f32>> = var.as_array_option().unwrap();
+    assert_eq!(values.as_slice().unwrap(), &[Some(1.), None, Some(3.), None, None, None]);
+
+    let var = file.root.variables.get("flags").unwrap();
+    let (values, mask) = var.as_array_masked::<u8>().unwrap();
+    assert_eq!(values.as_slice().unwrap(), &[0, 1, 0, 2, 0, 3]);
+    assert!(mask.iter().all(|m| !m));
+    let (_, mask) = var.as_array_masked::<i8>().unwrap();
+    assert!(mask.iter().all(|m| !m));
+}
+
+#[test]
+fn cf_times() {
+    use netcdf::time::{Calendar, Datetime};
+    let f = test_file_new("cf_times.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("time", 3).unwrap();
+        let var = file.root.create_variable("time", &["time".to_string()], netcdf_sys::NC_DOUBLE).unwrap();
+        var.add_attribute("units", "hours since 1970-01-01 00:00:00".to_string()).unwrap();
+        let times = [
+            Datetime::new(Calendar::Standard, 1970, 1, 2, 0, 0, 0).unwrap(),
+            Datetime::new(Calendar::Standard, 2000, 2, 29, 12, 30, 0).unwrap(),
+            // converted to the standard calendar
+            Datetime::new(Calendar::Julian, 1969, 12, 19, 6, 0, 0).unwrap(),
+        ];
+        var.put_times_at(&times, &[0], &[3]).unwrap();
+
+        let var = file.root.create_variable("model_time", &["time".to_string()], netcdf_sys::NC_INT).unwrap();
+        var.add_attribute("units", "days since 2000-01-01".to_string()).unwrap();
+        var.add_attribute("calendar", "360_day".to_string()).unwrap();
+        var.put_values_at(&[0i32, 59, 360], &[0], &[3]).unwrap();
+        let noleap = Datetime::new(Calendar::NoLeap, 2000, 3, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            var.put_times_at(&[noleap], &[0], &[1]),
+            Err(netcdf::Error::CalendarMismatch { expected: Calendar::Day360, found: Calendar::NoLeap })
+        );
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("time").unwrap();
+    assert_eq!(var.values::<f64>().unwrap(), vec![24., 264_396.5, 6.]);
+    let times: Vec<String> = var.times().unwrap().iter().map(|t| t.unwrap().to_string()).collect();
+    assert_eq!(times, vec!["1970-01-02 00:00:00", "2000-02-29 12:30:00", "1970-01-01 06:00:00"]);
+    let naive = var.times_at(&[1], &[1]).unwrap()[0].unwrap().to_naive_datetime().unwrap();
+    assert_eq!(naive.to_string(), "2000-02-29 12:30:00");
+
+    let var = file.root.variables.get("model_time").unwrap();
+    assert_eq!(var.time_units().unwrap().calendar(), Calendar::Day360);
+    let times: Vec<String> = var.times().unwrap().iter().map(|t| t.unwrap().to_string()).collect();
+    assert_eq!(times, vec!["2000-01-01 00:00:00", "2000-02-30 00:00:00", "2001-01-01 00:00:00"]);
+    assert_eq!(var.times().unwrap()[1].unwrap().to_naive_datetime(), None);
+}
+
+#[test]
+fn coordinate_selection() {
+    use netcdf::coordinate::Selection;
+    let f = test_file_new("coordinate_selection.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("time", 3).unwrap();
+        file.root.add_dimension("lat", 4).unwrap();
+        file.root.add_dimension("lon", 3).unwrap();
+        file.root.add_variable("time", &["time".to_string()], &vec![0f64, 24., 48.]).unwrap();
+        // decreasing coordinates
+        file.root.add_variable("lat", &["lat".to_string()], &vec![60f32, 45., 30., 15.]).unwrap();
+        file.root.add_variable("lon", &["lon".to_string()], &vec![0f32, 10., 20.]).unwrap();
+        let dims = ["time".to_string(), "lat".to_string(), "lon".to_string()];
+        let data: Vec<i32> = (0..36).collect();
+        file.root.add_variable("data", &dims, &data).unwrap();
+        // coordinates of the parent group
+        let grp = file.root.add_group("forecast").unwrap();
+        let lon = ["lon".to_string()];
+        grp.add_variable("wind", &lon, &vec![1.5f32, 2.5, 3.5]).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    assert_eq!(file.root.coordinate("lat").unwrap().unwrap().name, "lat");
+    assert!(file.root.coordinate("data").unwrap().is_none());
+    let grp = file.group("forecast").unwrap();
+    assert_eq!(grp.coordinate("lon").unwrap().unwrap().grp_id, file.root.id);
+    let wind = grp.variables.get("wind").unwrap();
+    let values: Vec<f32> = wind.values_selected(grp, &Selection::new().range("lon", 5., 25.)).unwrap();
+    assert_eq!(values, vec![2.5, 3.5]);
+    assert_eq!(wind.values_selected::<f32>(&file.root, &Selection::new()),
+               Err(netcdf::Error::NotInGroup { variable: "wind".to_string(), group: "root".to_string() }));
+
+    let var = file.root.variables.get("data").unwrap();
+    let selection = Selection::new()
+        .range("lat", 30., 45.)
+        .nearest("lon", 12.3)
+        .equal("time", 24.);
+    assert_eq!(var.selection_slice(&file.root, &selection).unwrap(),
+               (vec![1, 1, 1], vec![1, 2, 1]));
+    let values: ArrayD<i32> = var.array_selected(&file.root, &selection).unwrap();
+    assert_eq!(values.shape(), &[1, 2, 1]);
+    assert_eq!(values.as_slice().unwrap(), &[16, 19]);
+    let values: Vec<i32> = var.values_selected(&file.root, &Selection::new().range("time", 30., 100.)).unwrap();
+    assert_eq!(values, (24..36).collect::<Vec<i32>>());
+
+    assert_eq!(var.values_selected::<i32>(&file.root, &Selection::new().equal("time", 25.)),
+               Err(netcdf::Error::EmptySelection("time".to_string())));
+    assert_eq!(var.values_selected::<i32>(&file.root, &Selection::new().nearest("lon", f64::NAN)),
+               Err(netcdf::Error::EmptySelection("lon".to_string())));
+    let (indices, _) = var.selection_slice(&file.root, &Selection::new().nearest("lon", f64::INFINITY)).unwrap();
+    assert_eq!(indices, vec![0, 0, 2]);
+    assert_eq!(var.values_selected::<i32>(&file.root, &Selection::new().equal("depth", 0.)),
+               Err(netcdf::Error::DimensionNotFound("depth".to_string())));
+}
+
+#[test]
+fn cf_compliance() {
+    use netcdf::cf::{Severity, Violation};
+    let f = test_file_new("cf_compliance.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("time", 2).unwrap();
+        file.root.add_dimension("lat", 3).unwrap();
+        file.root.add_dimension("nv", 2).unwrap();
+        let text = |s: &str| s.to_string();
+
+        let var = file.root.create_variable("time", &[text("time")], netcdf_sys::NC_DOUBLE).unwrap();
+        var.add_attribute("units", text("hours since 1970-01-01")).unwrap();
+        var.add_attribute("bounds", text("time_bnds")).unwrap();
+        var.put_values_at(&[0f64, 24.], &[0], &[2]).unwrap();
+        file.root.create_variable("time_bnds", &[text("time"), text("nv")], netcdf_sys::NC_DOUBLE).unwrap();
+
+        let var = file.root.create_variable("lat", &[text("lat")], netcdf_sys::NC_FLOAT).unwrap();
+        var.add_attribute("units", text("degrees_north")).unwrap();
+        var.add_attribute("bounds", text("lat_bnds")).unwrap();
+        var.put_values_at(&[10f32, 30., 20.], &[0], &[3]).unwrap();
+        file.root.create_variable("lat_bnds", &[text("nv"), text("lat")], netcdf_sys::NC_FLOAT).unwrap();
+
+        let var = file.root.create_variable("temp", &[text("time"), text("lat")], netcdf_sys::NC_FLOAT).unwrap();
+        var.add_attribute("units", text("K")).unwrap();
+        var.add_attribute("standard_name", text("air temperature")).unwrap();
+        var.add_attribute("missing_value", -999i16).unwrap();
+        var.add_attribute("coordinates", text("lat height")).unwrap();
+
+        file.root.create_variable("counts", &[text("time")], netcdf_sys::NC_INT).unwrap();
+
+        let var = file.root.create_variable("bad_time", &[text("time")], netcdf_sys::NC_DOUBLE).unwrap();
+        var.add_attribute("units", text("days since yesterday")).unwrap();
+        var.add_attribute("calendar", text("lunar")).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let report = netcdf::cf::check(&file).unwrap();
+    let issues: Vec<(Severity, &str, Violation)> = report.issues.iter()
+        .map(|i| (i.severity, i.location.as_str(), i.violation.clone()))
+        .collect();
+    let s = |s: &str| s.to_string();
+    assert_eq!(issues, vec![
+        (Severity::Warning, "/", Violation::MissingConventions),
+        (Severity::Error, "/bad_time", Violation::InvalidCalendar(s("lunar"))),
+        (Severity::Error, "/bad_time", Violation::InvalidTimeUnits(s("days since yesterday"))),
+        (Severity::Warning, "/counts", Violation::MissingUnits),
+        (Severity::Error, "/lat", Violation::BoundsShape {
+            bounds: s("lat_bnds"), dimensions: vec![s("lat")], bounds_dimensions: vec![s("nv"), s("lat")] }),
+        (Severity::Error, "/lat", Violation::CoordinateNotMonotonic),
+        (Severity::Error, "/temp", Violation::AttributeType {
+            name: s("missing_value"), expected: netcdf_sys::NC_FLOAT, found: netcdf_sys::NC_SHORT }),
+        (Severity::Error, "/temp", Violation::InvalidStandardName(s("air temperature"))),
+        (Severity::Error, "/temp", Violation::MissingVariable { attribute: s("coordinates"), name: s("height") }),
+    ]);
+    assert!(!report.is_compliant());
+    assert_eq!(report.warnings().len(), 2);
+}
+
+#[test]
+fn copy_files() {
+    use netcdf::file::{CopyOptions, Format, Options};
+    use netcdf::variable::Compression;
+    let f = test_file_new("copy_files_input.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_attribute("title", "copy test".to_string()).unwrap();
+        file.root.add_attribute("version", 3i32).unwrap();
+        file.root.add_dimension("x", 4).unwrap();
+        file.root.add_unlimited_dimension("t").unwrap();
+        let var = file.root.create_variable("data", &["t".to_string(), "x".to_string()], netcdf_sys::NC_INT).unwrap();
+        var.add_attribute("units", "m".to_string()).unwrap();
+        let data: Vec<i32> = (0..20).collect();
+        var.put_values_at(&data, &[0, 0], &[5, 4]).unwrap();
+        let var = file.root.create_variable("names", &["x".to_string()], netcdf_sys::NC_STRING).unwrap();
+        var.put_strings_at(&["a", "bb", "", "dddd"], &[0], &[4]).unwrap();
+        let grp = file.root.add_group("sub").unwrap();
+        grp.add_dimension("y", 2).unwrap();
+        grp.add_variable("scaled", &["x".to_string(), "y".to_string()], &vec![1.5f64; 8]).unwrap();
+    }
+    let input = netcdf::open(&f).unwrap();
+
+    // whole file, recompressed and read 3 values at a time
+    let f_copy = test_file_new("copy_files_copy.nc");
+    let options = CopyOptions::new().deflate(4).shuffle(true).buffer_len(3);
+    netcdf::copy(&input, &f_copy, options).unwrap().close().unwrap();
+    let file = netcdf::open(&f_copy).unwrap();
+    assert_eq!(file.root.attributes.get("title").unwrap().get_char(false).unwrap(), "copy test");
+    assert_eq!(file.root.attributes.get("version").unwrap().get_int(false).unwrap(), 3);
+    assert!(file.root.dimensions.get("t").unwrap().is_unlimited());
+    let var = file.root.variables.get("data").unwrap();
+    assert_eq!(var.values::<i32>().unwrap(), (0..20).collect::<Vec<i32>>());
+    assert_eq!(var.attributes.get("units").unwrap().get_char(false).unwrap(), "m");
+    assert_eq!(var.compression().unwrap(), Compression { shuffle: true, deflate_level: Some(4) });
+    assert_eq!(file.root.variables.get("names").unwrap().get_strings().unwrap(),
+               vec!["a", "bb", "", "dddd"]);
+    let var = file.group("sub").unwrap().variables.get("scaled").unwrap();
+    assert_eq!(var.values::<f64>().unwrap(), vec![1.5; 8]);
+
+    // subset: global attributes and a single variable
+    let f_subset = test_file_new("copy_files_subset.nc");
+    {
+        let mut file = netcdf::create(&f_subset).unwrap();
+        file.root.copy_attributes_from(&input.root).unwrap();
+        let var = file.root.copy_variable_from(input.root.variables.get("data").unwrap()).unwrap();
+        assert_eq!(var.len(), 20);
+        let var = file.root.create_variable("data2", &["t".to_string(), "x".to_string()], netcdf_sys::NC_INT).unwrap();
+        var.copy_attributes_from(input.root.variables.get("data").unwrap()).unwrap();
+    }
+    let file = netcdf::open(&f_subset).unwrap();
+    assert_eq!(file.root.attributes.len(), 2);
+    assert_eq!(file.root.variables.len(), 2);
+    assert_eq!(file.root.dimensions.get("t").unwrap().len(), 5);
+    assert_eq!(file.root.variables.get("data").unwrap().values::<i32>().unwrap(), (0..20).collect::<Vec<i32>>());
+    assert_eq!(file.root.variables.get("data2").unwrap().attributes.get("units").unwrap().get_char(false).unwrap(), "m");
+
+    // classic files, in and out of define mode
+    let f_classic = test_file_new("copy_files_classic.nc");
+    let scaled = input.group("sub").unwrap().variables.get("scaled").unwrap();
+    {
+        let mut file = netcdf::create_with(&f_classic, Options::new().format(Format::Classic)).unwrap();
+        file.root.add_dimension("x", 3).unwrap();
+        assert_eq!(file.root.copy_variable_from(scaled).err(),
+                   Some(netcdf::Error::DimensionLen { name: "x".to_string(), len: 3, found: 4 }));
+        assert!(!file.root.variables.contains_key("scaled"));
+        assert!(!file.root.dimensions.contains_key("y"));
+    }
+    {
+        let mut file = netcdf::create_with(&f_classic, Options::new().format(Format::Classic)).unwrap();
+        file.root.copy_variable_from(input.root.variables.get("data").unwrap()).unwrap();
+        file.root.add_dimension("y", 2).unwrap();
+    }
+    {
+        let mut file = netcdf::append(&f_classic).unwrap();
+        file.root.copy_attributes_from(&input.root).unwrap();
+        file.root.copy_variable_from(scaled).unwrap();
+    }
+    let file = netcdf::open(&f_classic).unwrap();
+    assert_eq!(file.root.attributes.len(), 2);
+    assert_eq!(file.root.variables.get("data").unwrap().values::<i32>().unwrap(), (0..20).collect::<Vec<i32>>());
+    assert_eq!(file.root.variables.get("scaled").unwrap().values::<f64>().unwrap(), vec![1.5; 8]);
+}
+
+#[test]
+fn rename_and_delete() {
+    use netcdf::file::{Format, Options};
+    let f = test_file_new("rename_and_delete.nc");
+    {
+        let mut file = netcdf::create(&f).unwrap();
+        file.root.add_dimension("x", 3).unwrap();
+        file.root.add_attribute("title", "renamed".to_string()).unwrap();
+        file.root.add_attribute("history", "deleted".to_string()).unwrap();
+        file.root.add_variable("temp", &["x".to_string()], &vec![1i32, 2, 3]).unwrap();
+        let grp = file.root.add_group("grp").unwrap();
+        grp.add_variable("data", &["x".to_string()], &vec![4i32, 5, 6]).unwrap();
+
+        file.root.rename_attribute("title", "long_title").unwrap();
+        file.root.delete_attribute("history").unwrap();
+        file.root.rename_variable("temp", "temperature").unwrap();
+        file.root.rename_dimension("x", "lon").unwrap();
+        file.root.rename_group("grp", "science").unwrap();
+        assert_eq!(file.root.rename_variable("temp", "t"),
+                   Err(netcdf::Error::VariableNotFound("temp".to_string())));
+        assert_eq!(file.root.delete_attribute("history"),
+                   Err(netcdf::Error::AttributeNotFound("history".to_string())));
+        let nul = "bad\0name".to_string();
+        assert_eq!(file.root.rename_variable("temperature", &nul), Err(netcdf::Error::InteriorNul(nul.clone())));
+        assert_eq!(file.root.rename_dimension("lon", &nul), Err(netcdf::Error::InteriorNul(nul.clone())));
+        assert_eq!(file.root.rename_group("science", &nul), Err(netcdf::Error::InteriorNul(nul.clone())));
+        assert_eq!(file.root.rename_attribute("long_title", &nul), Err(netcdf::Error::InteriorNul(nul.clone())));
+
+        // the cached maps follow
+        assert_eq!(file.root.attributes.keys().collect::<Vec<_>>(), vec!["long_title"]);
+        let var = file.root.variables.get("temperature").unwrap();
+        assert_eq!(var.name, "temperature");
+        assert_eq!(var.dimensions[0].name, "lon");
+        let grp = file.group("science").unwrap();
+        assert_eq!(grp.name, "science");
+        assert!(grp.dimensions.contains_key("lon") && !grp.dimensions.contains_key("x"));
+        assert_eq!(grp.variables.get("data").unwrap().dimensions[0].name, "lon");
+    }
+    let file = netcdf::open(&f).unwrap();
+    assert_eq!(file.root.attributes.get("long_title").unwrap().get_char(false).unwrap(), "renamed");
+    assert!(!file.root.attributes.contains_key("history"));
+    assert_eq!(file.root.variables.get("temperature").unwrap().values::<i32>().unwrap(), vec![1, 2, 3]);
+    assert_eq!(file.root.dimensions.get("lon").unwrap().len(), 3);
+    assert_eq!(file.group("science").unwrap().variables.get("data").unwrap().values::<i32>().unwrap(),
+               vec![4, 5, 6]);
+
+    // classic files are switched to define mode in append mode
+    let f = test_file_new("rename_and_delete_classic.nc");
+    {
+        let mut file = netcdf::create_with(&f, Options::new().format(Format::Classic)).unwrap();
+        file.root.add_dimension("x", 2).unwrap();
+        let var = file.root.create_variable("v", &["x".to_string()], netcdf_sys::NC_INT).unwrap();
+        var.add_attribute("units", "m".to_string()).unwrap();
+        var.add_attribute("comment", "deleted".to_string()).unwrap();
+    }
+    {
+        let mut file = netcdf::append(&f).unwrap();
+        file.root.rename_variable("v", "values").unwrap();
+        file.root.rename_dimension("x", "n").unwrap();
+        let var = file.root.variables.get_mut("values").unwrap();
+        var.rename_attribute("units", "unit").unwrap();
+        var.delete_attribute("comment").unwrap();
+        // back in data mode
+        var.put_values_at(&[7i32, 8], &[0], &[2]).unwrap();
+    }
+    let file = netcdf::open(&f).unwrap();
+    let var = file.root.variables.get("values").unwrap();
+    assert_eq!(var.values::<i32>().unwrap(), vec![7, 8]);
+    assert_eq!(var.dimensions[0].name, "n");
+    assert_eq!(var.attributes.keys().collect::<Vec<_>>(), vec!["unit"]);
+}
+
 #[test]
 /// Tests the shape of a variable
 /// when fetched using "Variable::as_array()"
@@ -671,6 +1622,47 @@ fn put_values() {
     );
 }
 
+#[test]
+/// Test growing a variable along an unlimited dimension
+fn unlimited_dimension() {
+    let f = test_file_new("unlimited_dimension.nc");
+    let dim_name = "time";
+    let var_name = "temperature";
+    {
+        let mut file_w = netcdf::create(&f).unwrap();
+        file_w.root.add_unlimited_dimension(dim_name).unwrap();
+        assert!(file_w.root.dimensions.get(dim_name).unwrap().is_unlimited());
+        assert_eq!(file_w.root.dimensions.get(dim_name).unwrap().len(), 0);
+
+        file_w.root.create_variable("pressure", &[dim_name.into()], netcdf_sys::NC_FLOAT).unwrap();
+        {
+            let var = file_w.root.create_variable(
+                var_name, &[dim_name.into()], netcdf_sys::NC_FLOAT).unwrap();
+            var.put_values_at(&[1f32, 2.], &[0], &[2]).unwrap();
+            assert_eq!(var.len(), 2);
+            var.put_value_at(3f32, &[2]).unwrap();
+            assert_eq!(var.len(), 3);
+            assert_eq!(var.dimensions[0].len(), 3);
+        }
+        // the group and the variables sharing the record dimension see the new length
+        assert_eq!(file_w.root.dimensions.get(dim_name).unwrap().len(), 3);
+        assert_eq!(file_w.root.variables.get("pressure").unwrap().len(), 3);
+    }
+    {
+        // append two more records
+        let mut file_a = netcdf::append(&f).unwrap();
+        assert!(file_a.root.dimensions.get(dim_name).unwrap().is_unlimited());
+        let var = file_a.root.variables.get_mut(var_name).unwrap();
+        assert_eq!(var.len(), 3);
+        var.put_values_at(&[4f32, 5.], &[3], &[2]).unwrap();
+        assert_eq!(var.len(), 5);
+    }
+    let file = netcdf::open(&f).unwrap();
+    assert_eq!(file.root.dimensions.get(dim_name).unwrap().len(), 5);
+    let var = file.root.variables.get(var_name).unwrap();
+    assert_eq!(var.values::<f32>().unwrap(), vec![1., 2., 3., 4., 5.]);
+}
+
 #[test]
 /// Test setting a fill value when creating a Variable
 fn set_fill_value() {
@@ -700,8 +1692,8 @@ fn read_values_into_buffer() {
     let file = netcdf::open(&f).unwrap();
     let var = file.root.variables.get("data").unwrap();
     // pre-allocate the Array
-    let mut data: Vec<i32> = Vec::with_capacity(var.len as usize);
-    var.read_values_into_buffer(&mut data);
+    let mut data: Vec<i32> = Vec::with_capacity(var.len() as usize);
+    var.read_values_into_buffer(&mut data).unwrap();
 
     assert_eq!(data.len(), 6*12);
     for x in 0..(6*12) {